@@ -0,0 +1,299 @@
+//! Data-driven sprite animation.
+//!
+//! An [`AnimationLibrary`] asset describes the grid layout of a sprite sheet
+//! and a set of named [`SpriteClip`]s. A [`SpriteAnimator`] plays one of those
+//! clips on the [`Sprite`] of its entity, so any character can be animated
+//! without writing new animation code. Libraries are loaded from
+//! `*.anim.ron` files.
+//!
+//! Clips can tag frames with named events. Whenever such a frame is reached, a
+//! [`SpriteAnimationEvent`] is triggered on the animated entity, so gameplay,
+//! audio and effects can react to the animation without knowing its frames.
+
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{asset::LoadContext, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    ron_asset::{InitRonAsset, RonAsset},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<AnimationLibrary>();
+    app.register_type::<SpriteAnimator>();
+    app.register_type::<SpriteAnimationEvent>();
+    app.init_ron_asset::<AnimationLibrary>();
+
+    app.add_systems(
+        Update,
+        tick_sprite_animators
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems),
+    );
+    // Apply frames late, so clips changed during `Update` are shown immediately.
+    app.add_systems(PostUpdate, apply_sprite_animator_frames);
+}
+
+/// A sprite sheet layout and the clips that can be played on it.
+#[derive(Asset, Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct AnimationLibrary {
+    /// The grid layout of the sprite sheet.
+    pub layout: AtlasGrid,
+    /// The available clips, by name.
+    pub clips: HashMap<String, SpriteClip>,
+    /// The [`TextureAtlasLayout`] created from [`Self::layout`] when loading.
+    #[serde(skip)]
+    pub atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+impl RonAsset for AnimationLibrary {
+    const EXTENSIONS: &'static [&'static str] = &["anim.ron"];
+
+    fn load_dependencies(&mut self, load_context: &mut LoadContext) {
+        self.atlas_layout =
+            load_context.add_labeled_asset("layout".to_string(), self.layout.to_layout());
+    }
+}
+
+impl AnimationLibrary {
+    pub fn clip(&self, name: &str) -> Option<&SpriteClip> {
+        self.clips.get(name)
+    }
+}
+
+/// A uniform grid of sprites in a texture.
+/// See [`TextureAtlasLayout::from_grid`] for the meaning of each field.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct AtlasGrid {
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    #[serde(default)]
+    pub padding: Option<UVec2>,
+    #[serde(default)]
+    pub offset: Option<UVec2>,
+}
+
+impl AtlasGrid {
+    pub fn to_layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(
+            self.tile_size,
+            self.columns,
+            self.rows,
+            self.padding,
+            self.offset,
+        )
+    }
+}
+
+/// A range of frames in the atlas, played in order.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct SpriteClip {
+    /// Atlas index of the first frame.
+    pub first: usize,
+    /// Atlas index of the last frame (inclusive).
+    pub last: usize,
+    /// How long each frame is shown.
+    pub durations: FrameDurations,
+    /// What happens after the last frame.
+    #[serde(default)]
+    pub mode: LoopMode,
+    /// Named events fired when a frame is reached, keyed by the frame relative
+    /// to the start of the clip.
+    #[serde(default)]
+    pub events: HashMap<usize, Vec<String>>,
+}
+
+impl SpriteClip {
+    /// The number of frames in this clip.
+    pub fn frame_count(&self) -> usize {
+        self.last.saturating_sub(self.first) + 1
+    }
+
+    /// Atlas index of a frame in this clip.
+    pub fn atlas_index(&self, frame: usize) -> usize {
+        self.first + frame.min(self.frame_count() - 1)
+    }
+
+    /// How long a frame in this clip is shown.
+    pub fn duration(&self, frame: usize) -> Duration {
+        let millis = match &self.durations {
+            FrameDurations::Uniform(millis) => *millis,
+            FrameDurations::PerFrame(millis) => millis
+                .get(frame)
+                .or(millis.last())
+                .copied()
+                .unwrap_or_default(),
+        };
+        Duration::from_millis(millis)
+    }
+}
+
+/// Frame durations in milliseconds.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
+pub enum FrameDurations {
+    /// Every frame is shown for the same time.
+    Uniform(u64),
+    /// Each frame has its own duration. Missing entries reuse the last one.
+    PerFrame(Vec<u64>),
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Start over from the first frame.
+    #[default]
+    Loop,
+    /// Stop at the last frame.
+    Once,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+/// Triggered on an entity when its [`SpriteAnimator`] reaches a frame tagged
+/// with an event in its [`SpriteClip`].
+#[derive(Event, Reflect, Clone, Debug)]
+pub struct SpriteAnimationEvent {
+    pub name: String,
+}
+
+/// Plays a [`SpriteClip`] from an [`AnimationLibrary`] on the [`Sprite`] of this entity.
+/// The sprite's texture atlas should use the library's [`AnimationLibrary::atlas_layout`].
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct SpriteAnimator {
+    pub library: Handle<AnimationLibrary>,
+    clip: String,
+    frame: usize,
+    elapsed: Duration,
+    reversed: bool,
+    finished: bool,
+    /// Whether the first frame of the clip still has to be reported as reached.
+    started: bool,
+}
+
+impl SpriteAnimator {
+    pub fn new(library: Handle<AnimationLibrary>, clip: impl Into<String>) -> Self {
+        Self {
+            library,
+            clip: clip.into(),
+            frame: 0,
+            elapsed: Duration::ZERO,
+            reversed: false,
+            finished: false,
+            started: true,
+        }
+    }
+
+    /// Switch to another clip, starting from its first frame.
+    /// Does nothing if the clip is already playing.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            *self = Self::new(self.library.clone(), clip);
+        }
+    }
+
+    /// The name of the playing clip.
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    /// The current frame, relative to the start of the clip.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Advance the animation. `on_frame` is called with every frame that is
+    /// reached, even if multiple frames are passed in a single tick.
+    pub fn tick(&mut self, delta: Duration, clip: &SpriteClip, mut on_frame: impl FnMut(usize)) {
+        if std::mem::take(&mut self.started) {
+            on_frame(self.frame);
+        }
+        self.elapsed += delta;
+        while !self.finished {
+            let duration = clip.duration(self.frame);
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+            if self.advance(clip) {
+                on_frame(self.frame);
+            }
+            // Prevent an endless loop on zero-length frames.
+            if duration.is_zero() {
+                break;
+            }
+        }
+    }
+
+    /// Move to the next frame. Returns whether the frame changed.
+    fn advance(&mut self, clip: &SpriteClip) -> bool {
+        let last = clip.frame_count() - 1;
+        match clip.mode {
+            LoopMode::Loop => self.frame = (self.frame + 1) % (last + 1),
+            LoopMode::Once if self.frame >= last => {
+                self.finished = true;
+                return false;
+            }
+            LoopMode::Once => self.frame += 1,
+            LoopMode::PingPong if last == 0 => return false,
+            LoopMode::PingPong => {
+                if self.frame == 0 {
+                    self.reversed = false;
+                } else if self.frame >= last {
+                    self.reversed = true;
+                }
+                self.frame = if self.reversed {
+                    self.frame - 1
+                } else {
+                    self.frame + 1
+                };
+            }
+        }
+        true
+    }
+}
+
+fn tick_sprite_animators(
+    mut commands: Commands,
+    time: Res<Time>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut animator_query: Query<(Entity, &mut SpriteAnimator)>,
+) {
+    for (entity, mut animator) in &mut animator_query {
+        let Some(clip) = libraries
+            .get(&animator.library)
+            .and_then(|library| library.clip(&animator.clip))
+        else {
+            continue;
+        };
+        animator.tick(time.delta(), clip, |frame| {
+            for name in clip.events.get(&frame).into_iter().flatten() {
+                commands.trigger_targets(SpriteAnimationEvent { name: name.clone() }, entity);
+            }
+        });
+    }
+}
+
+/// Update the texture atlas to reflect changes in the animation.
+fn apply_sprite_animator_frames(
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut animator_query: Query<(&SpriteAnimator, &mut Sprite)>,
+) {
+    for (animator, mut sprite) in &mut animator_query {
+        let Some(clip) = libraries
+            .get(&animator.library)
+            .and_then(|library| library.clip(&animator.clip))
+        else {
+            continue;
+        };
+        let Some(atlas) = sprite.texture_atlas.as_mut() else {
+            continue;
+        };
+        let index = clip.atlas_index(animator.frame);
+        if atlas.index != index {
+            atlas.index = index;
+        }
+    }
+}
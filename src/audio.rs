@@ -1,15 +1,38 @@
-use bevy::prelude::*;
+use bevy::{audio::Volume, prelude::*};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Music>();
     app.register_type::<SoundEffect>();
+    app.register_type::<UiSound>();
+
+    app.register_type::<MusicVolume>();
+    app.register_type::<SoundEffectVolume>();
+    app.register_type::<UiVolume>();
+    app.init_resource::<MusicVolume>();
+    app.init_resource::<SoundEffectVolume>();
+    app.init_resource::<UiVolume>();
+    app.register_type::<Muted>();
+    app.init_resource::<Muted>();
 
     app.add_systems(
         Update,
-        apply_global_volume.run_if(resource_changed::<GlobalVolume>),
+        (
+            apply_channel_volume::<Music>,
+            apply_channel_volume::<SoundEffect>,
+            apply_channel_volume::<UiSound>,
+            apply_global_volume,
+        ),
     );
 }
 
+/// An audio category with its own volume. The category's volume is applied on top of
+/// [`GlobalVolume`], which acts as the master volume. An [`AudioPlayer`] without a
+/// category only follows the [`GlobalVolume`].
+pub trait AudioChannel: Component {
+    /// The resource holding the volume of this category.
+    type Volume: Resource + std::ops::Deref<Target = Volume>;
+}
+
 /// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's in the
 /// general "music" category (e.g. global background music, soundtrack).
 ///
@@ -18,6 +41,10 @@ pub(super) fn plugin(app: &mut App) {
 #[reflect(Component)]
 pub struct Music;
 
+impl AudioChannel for Music {
+    type Volume = MusicVolume;
+}
+
 /// A music audio instance.
 pub fn music(handle: Handle<AudioSource>) -> impl Bundle {
     (AudioPlayer(handle), PlaybackSettings::LOOP, Music)
@@ -31,17 +58,78 @@ pub fn music(handle: Handle<AudioSource>) -> impl Bundle {
 #[reflect(Component)]
 pub struct SoundEffect;
 
+impl AudioChannel for SoundEffect {
+    type Volume = SoundEffectVolume;
+}
+
 /// A sound effect audio instance.
 pub fn sound_effect(handle: Handle<AudioSource>) -> impl Bundle {
     (AudioPlayer(handle), PlaybackSettings::DESPAWN, SoundEffect)
 }
 
-/// [`GlobalVolume`] doesn't apply to already-running audio entities, so this system will update them.
+/// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's in the
+/// general "user interface" category (e.g. button hovers and clicks).
+///
+/// This can then be used to query for and operate on sounds in that category.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct UiSound;
+
+impl AudioChannel for UiSound {
+    type Volume = UiVolume;
+}
+
+/// A user interface sound instance.
+pub fn ui_sound(handle: Handle<AudioSource>) -> impl Bundle {
+    (AudioPlayer(handle), PlaybackSettings::DESPAWN, UiSound)
+}
+
+/// The volume of all [`Music`].
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, Deref, DerefMut)]
+#[reflect(Resource)]
+pub struct MusicVolume(pub Volume);
+
+/// The volume of all [`SoundEffect`]s.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, Deref, DerefMut)]
+#[reflect(Resource)]
+pub struct SoundEffectVolume(pub Volume);
+
+/// The volume of all [`UiSound`]s.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, Deref, DerefMut)]
+#[reflect(Resource)]
+pub struct UiVolume(pub Volume);
+
+/// Silences all audio without changing the volume [`Settings`](crate::settings::Settings).
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct Muted(pub bool);
+
+/// [`GlobalVolume`] and the channel volumes don't apply to already-running audio entities,
+/// and newly spawned ones only receive the [`GlobalVolume`], so this system will update them.
+fn apply_channel_volume<C: AudioChannel>(
+    global_volume: Res<GlobalVolume>,
+    channel_volume: Res<C::Volume>,
+    mut audio_query: Query<(&PlaybackSettings, &mut AudioSink), With<C>>,
+) {
+    let volume_changed = global_volume.is_changed() || channel_volume.is_changed();
+    for (playback, mut sink) in &mut audio_query {
+        if volume_changed || sink.is_added() {
+            sink.set_volume(global_volume.volume * **channel_volume * playback.volume);
+        }
+    }
+}
+
+/// Like [`apply_channel_volume`], for audio entities without a category.
 fn apply_global_volume(
     global_volume: Res<GlobalVolume>,
-    mut audio_query: Query<(&PlaybackSettings, &mut AudioSink)>,
+    mut audio_query: Query<
+        (&PlaybackSettings, &mut AudioSink),
+        (Without<Music>, Without<SoundEffect>, Without<UiSound>),
+    >,
 ) {
     for (playback, mut sink) in &mut audio_query {
-        sink.set_volume(global_volume.volume * playback.volume);
+        if global_volume.is_changed() || sink.is_added() {
+            sink.set_volume(global_volume.volume * playback.volume);
+        }
     }
 }
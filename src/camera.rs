@@ -0,0 +1,218 @@
+//! The main camera.
+//!
+//! A [`CameraController`] follows the entity marked as [`CameraTarget`]:
+//! - The target can move freely within a dead zone without moving the camera.
+//! - The camera eases towards the target instead of snapping to it.
+//! - The camera looks ahead in the direction the target wants to move in.
+//! - The camera never shows anything outside of the [`LevelBounds`].
+//!
+//! Any system can shake the camera by sending a [`ScreenShake`] event. Shakes
+//! add trauma, which decays over time, so repeated shakes stack up.
+
+use bevy::{prelude::*, render::camera::Projection};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{level::LevelBounds, movement::MovementController},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CameraController>();
+    app.register_type::<CameraShake>();
+    app.register_type::<CameraTarget>();
+    app.add_event::<ScreenShake>();
+
+    app.add_systems(Startup, spawn_camera);
+    app.add_systems(
+        Update,
+        (
+            decay_trauma
+                .in_set(AppSystems::TickTimers)
+                .in_set(PausableSystems),
+            add_trauma.in_set(AppSystems::Update),
+        ),
+    );
+    // Follow the target after it has moved, but before transforms are propagated.
+    app.add_systems(
+        PostUpdate,
+        (follow_target, apply_camera_shake)
+            .chain()
+            .before(TransformSystem::TransformPropagate),
+    );
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Camera"),
+        Camera2d,
+        CameraController::default(),
+        CameraShake::default(),
+    ));
+}
+
+/// Marks the entity that the camera follows.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct CameraTarget;
+
+/// Makes a camera follow the [`CameraTarget`].
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct CameraController {
+    /// Half the size of the area around the camera in which the target can
+    /// move without moving the camera, in world units.
+    pub dead_zone: Vec2,
+    /// How quickly the camera catches up with the target. Higher is faster.
+    pub decay_rate: f32,
+    /// How far ahead of the target to look, in world units.
+    pub look_ahead: f32,
+    /// The center of the dead zone.
+    focus: Vec2,
+    /// Where the camera would be without any shake.
+    anchor: Vec2,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            dead_zone: Vec2::new(96.0, 64.0),
+            decay_rate: 6.0,
+            look_ahead: 160.0,
+            focus: Vec2::ZERO,
+            anchor: Vec2::ZERO,
+        }
+    }
+}
+
+/// Trauma-based screen shake, see [GDC: Math for Game Programmers: Juicing Your Cameras With Math](https://www.youtube.com/watch?v=tu-Qe66AvtY).
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct CameraShake {
+    /// Between 0 and 1. The shake grows with the square of the trauma.
+    pub trauma: f32,
+    /// How much trauma is lost per second.
+    pub decay: f32,
+    /// The offset at full trauma, in world units.
+    pub max_offset: f32,
+    /// The rotation at full trauma, in radians.
+    pub max_angle: f32,
+    /// How quickly the shake changes direction.
+    pub frequency: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.5,
+            max_offset: 24.0,
+            max_angle: 0.05,
+            frequency: 25.0,
+        }
+    }
+}
+
+/// Send this event to shake the camera.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ScreenShake {
+    /// How much trauma to add, between 0 and 1.
+    pub trauma: f32,
+}
+
+fn follow_target(
+    time: Res<Time>,
+    target_query: Query<
+        (&Transform, Option<&MovementController>, Ref<CameraTarget>),
+        Without<CameraController>,
+    >,
+    bounds_query: Query<&LevelBounds>,
+    mut camera_query: Query<(&mut CameraController, &Projection)>,
+) {
+    let Some((target_transform, controller, target)) = target_query.iter().next() else {
+        return;
+    };
+    // Levels are spawned at the origin, so this is the same as the global translation,
+    // but without lagging a frame behind.
+    let target_position = target_transform.translation.xy();
+
+    for (mut camera, projection) in &mut camera_query {
+        // Keep the target within the dead zone.
+        let offset = target_position - camera.focus;
+        let dead_zone = camera.dead_zone;
+        camera.focus += offset - offset.clamp(-dead_zone, dead_zone);
+        if target.is_added() {
+            camera.focus = target_position;
+        }
+
+        let mut goal = camera.focus;
+        if let Some(controller) = controller {
+            goal += controller.intent * camera.look_ahead;
+        }
+
+        let goal = match (projection, bounds_query.iter().next()) {
+            (Projection::Orthographic(projection), Some(bounds)) => {
+                clamp_to_bounds(goal, projection.area.half_size(), bounds.0)
+            }
+            _ => goal,
+        };
+
+        // Snap to new targets, such as the player of a level that was just spawned.
+        camera.anchor = if target.is_added() {
+            goal
+        } else {
+            let decay_rate = camera.decay_rate;
+            camera
+                .anchor
+                .lerp(goal, 1.0 - (-decay_rate * time.delta_secs()).exp())
+        };
+    }
+}
+
+/// Keep the visible area within the bounds, or centered on them if they are
+/// smaller than the visible area.
+fn clamp_to_bounds(position: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    Vec2::new(
+        if min.x <= max.x {
+            position.x.clamp(min.x, max.x)
+        } else {
+            bounds.center().x
+        },
+        if min.y <= max.y {
+            position.y.clamp(min.y, max.y)
+        } else {
+            bounds.center().y
+        },
+    )
+}
+
+fn add_trauma(mut events: EventReader<ScreenShake>, mut camera_query: Query<&mut CameraShake>) {
+    for event in events.read() {
+        for mut shake in &mut camera_query {
+            shake.trauma = (shake.trauma + event.trauma).clamp(0.0, 1.0);
+        }
+    }
+}
+
+fn decay_trauma(time: Res<Time>, mut camera_query: Query<&mut CameraShake>) {
+    for mut shake in &mut camera_query {
+        shake.trauma = (shake.trauma - shake.decay * time.delta_secs()).max(0.0);
+    }
+}
+
+fn apply_camera_shake(
+    time: Res<Time>,
+    mut camera_query: Query<(&CameraController, &CameraShake, &mut Transform)>,
+) {
+    for (camera, shake, mut transform) in &mut camera_query {
+        let amount = shake.trauma * shake.trauma;
+        let t = time.elapsed_secs() * shake.frequency;
+        // Cheap, smooth noise: a few sine waves at unrelated frequencies.
+        let noise = |seed: f32| ((t + seed).sin() + (2.3 * t + seed * 1.7).sin()) / 2.0;
+
+        let offset = Vec2::new(noise(0.0), noise(10.0)) * shake.max_offset * amount;
+        transform.translation = (camera.anchor + offset).extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(noise(20.0) * shake.max_angle * amount);
+    }
+}
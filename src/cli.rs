@@ -0,0 +1,196 @@
+//! Command-line options, mostly to skip straight to the part of the game that
+//! is being worked on.
+//!
+//! Run with `--help` to list them, e.g. `--level meadow --seed 42 --mute`.
+
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_console::{ConsoleCommandEntered, ConsoleSet};
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    audio::Muted,
+    demo::{progression::CurrentLevel, store::Store},
+    replay::PlayReplay,
+    rng::RngSeed,
+    save::SaveDir,
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    let cli = app.world_mut().get_resource_or_init::<Cli>().clone();
+
+    if let Some(seed) = cli.seed {
+        app.insert_resource(RngSeed(seed));
+    }
+    if cli.mute {
+        app.insert_resource(Muted(true));
+    }
+    if let Some(dir) = cli.save_dir {
+        app.insert_resource(SaveDir(Some(dir)));
+    }
+    if cli.level.is_some() {
+        app.add_systems(
+            OnEnter(Screen::Gameplay),
+            select_start_level.run_if(run_once),
+        );
+    }
+    if let Some(path) = cli.replay {
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.trigger(PlayReplay { path: path.clone() });
+        });
+    }
+    if let Some(path) = cli.exec {
+        match fs::read_to_string(&path) {
+            Ok(script) => {
+                app.insert_resource(ExecScript(script));
+                app.add_systems(
+                    Update,
+                    run_exec_script
+                        .run_if(resource_exists::<ExecScript>)
+                        .before(ConsoleSet::Commands),
+                );
+            }
+            Err(error) => error!("Failed to read {}: {error}", path.display()),
+        }
+    }
+}
+
+/// The command-line options the game was started with.
+#[derive(Parser, Resource, Clone, Debug, Default)]
+#[command(version)]
+pub struct Cli {
+    /// Skip the splash screen and start at the title screen
+    #[arg(long)]
+    pub skip_splash: bool,
+    /// The screen to start at
+    #[arg(long, value_enum)]
+    pub screen: Option<StartScreen>,
+    /// The id of the level to start at, implies `--screen gameplay`
+    #[arg(long, value_name = "ID")]
+    pub level: Option<String>,
+    /// The seed for all randomness
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// The size of the window, e.g. `1280x720`
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    pub windowed: Option<UVec2>,
+    /// Mute all audio without changing the volume settings
+    #[arg(long)]
+    pub mute: bool,
+    /// A file with console commands to run once the start screen is reached,
+    /// one per line
+    #[arg(long, value_name = "SCRIPT")]
+    pub exec: Option<PathBuf>,
+    /// A replay to play back, implies `--screen gameplay`
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// The directory to keep saves in, instead of the user data directory
+    #[arg(long, value_name = "DIR")]
+    pub save_dir: Option<PathBuf>,
+}
+
+/// The screens that the game can start at.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartScreen {
+    Splash,
+    Title,
+    Gameplay,
+}
+
+impl StartScreen {
+    pub fn screen(self) -> Screen {
+        match self {
+            Self::Splash => Screen::Splash,
+            Self::Title => Screen::Title,
+            Self::Gameplay => Screen::Gameplay,
+        }
+    }
+}
+
+impl Cli {
+    /// The screen that the game should be started at.
+    pub fn initial_screen(&self) -> Screen {
+        match self.start_screen() {
+            // Make sure all assets have been loaded first.
+            StartScreen::Gameplay => Screen::Loading,
+            screen => screen.screen(),
+        }
+    }
+
+    fn start_screen(&self) -> StartScreen {
+        if let Some(screen) = self.screen {
+            screen
+        } else if self.level.is_some() || self.replay.is_some() {
+            StartScreen::Gameplay
+        } else if self.skip_splash {
+            StartScreen::Title
+        } else {
+            StartScreen::Splash
+        }
+    }
+}
+
+fn parse_size(text: &str) -> Result<UVec2, String> {
+    let (width, height) = text
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, found `{text}`"))?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<u32>()
+            .map_err(|error| format!("invalid size `{value}`: {error}"))
+    };
+    Ok(UVec2::new(parse(width)?, parse(height)?))
+}
+
+/// Runs before the level is spawned, so the level is spawned right away.
+fn select_start_level(cli: Res<Cli>, store: Store, mut current_level: ResMut<CurrentLevel>) {
+    let Some(id) = &cli.level else {
+        return;
+    };
+    let Some(list) = store.level_lists.get(&store.assets.levels) else {
+        return;
+    };
+    match list.index_of(id) {
+        Some(index) => current_level.0 = index,
+        None => warn!("There is no level with id {id:?}."),
+    }
+}
+
+/// The contents of the `--exec` script, until it has been run.
+#[derive(Resource)]
+struct ExecScript(String);
+
+fn run_exec_script(
+    mut commands: Commands,
+    cli: Res<Cli>,
+    script: Res<ExecScript>,
+    screen: Res<State<Screen>>,
+    mut console_commands: EventWriter<ConsoleCommandEntered>,
+) {
+    if screen.get() != &cli.start_screen().screen() {
+        return;
+    }
+
+    for line in script.0.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some(mut words) = shlex::split(line) else {
+            warn!("Skipping malformed console command: {line}");
+            continue;
+        };
+        if words.is_empty() {
+            continue;
+        }
+        info!("Running console command: {line}");
+        let command_name = words.remove(0);
+        console_commands.write(ConsoleCommandEntered {
+            command_name,
+            args: words,
+        });
+    }
+    commands.remove_resource::<ExecScript>();
+}
@@ -8,16 +8,23 @@ use bevy_console::{
 use bevy_egui::egui::Color32;
 use clap::Parser;
 
+use crate::input::{Action, ActionBindings};
+
 const PADDING: f32 = 50.0;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(Console::plugin);
 }
 
-/// TODO: disable controls if this is present
-/// TODO: rewire print statements to this ingame console
+/// What console commands need to be registered and run, without the console
+/// itself, which [`ConsolePlugin`] adds on top.
+pub fn commands_plugin(app: &mut App) {
+    app.init_resource::<ConsoleConfiguration>();
+    app.add_event::<ConsoleCommandEntered>();
+    app.add_event::<PrintConsoleLine>();
+}
+
 /// TODO: fullscreen or something?
-/// TODO: store log to file
 #[derive(SystemParam)]
 pub struct Console<'w> {
     print_line_event: EventWriter<'w, PrintConsoleLine>,
@@ -32,7 +39,6 @@ impl<'w> Console<'w> {
     pub fn plugin(app: &mut App) {
         app.add_plugins(ConsolePlugin)
             .insert_resource(ConsoleConfiguration {
-                keys: vec![KeyCode::Backquote],
                 left_pos: PADDING,
                 top_pos: PADDING,
                 history_size: 20,
@@ -53,9 +59,14 @@ impl<'w> Console<'w> {
             .add_systems(Startup, Self::startup_driver)
             .add_systems(
                 Update,
-                Self::write_to_console_driver.after(ConsoleSet::ConsoleUI),
+                Self::bindings_driver.run_if(resource_exists_and_changed::<ActionBindings>),
+            )
+            .add_systems(
+                Update,
+                Self::resize_driver
+                    .before(ConsoleSet::ConsoleUI)
+                    .run_if(any_with_component::<Window>),
             )
-            .add_systems(Update, Self::resize_driver.before(ConsoleSet::ConsoleUI))
             .add_systems(
                 Update,
                 Self::raw_commands_driver.in_set(ConsoleSet::Commands),
@@ -65,15 +76,10 @@ impl<'w> Console<'w> {
 
     fn raw_commands_driver(mut console_commands: EventReader<ConsoleCommandEntered>) {
         for ConsoleCommandEntered { command_name, args } in console_commands.read() {
-            println!(r#"Entered command "{command_name}" with args {:#?}"#, args);
+            debug!("Entered command {command_name:?} with args {args:?}");
         }
     }
 
-    // a dummy to remember that anything printing to console should go after consoleUI
-    pub fn write_to_console_driver() {
-        // console_line.write(PrintConsoleLine::new("Hello".into()));
-    }
-
     pub fn resize_driver(
         mut config: ResMut<ConsoleConfiguration>,
         query: Query<&Window>,
@@ -84,6 +90,15 @@ impl<'w> Console<'w> {
         Ok(())
     }
 
+    /// The console listens for key presses by itself, so keep its keys in sync with
+    /// the [`Action::ToggleConsole`] bindings. Non-keyboard bindings are ignored.
+    pub fn bindings_driver(
+        bindings: Res<ActionBindings>,
+        mut config: ResMut<ConsoleConfiguration>,
+    ) {
+        config.keys = bindings.keys(Action::ToggleConsole).collect();
+    }
+
     pub fn startup_driver(mut console: Console) {
         // reply!(log, "my bevy is ready");
         console.log("My Bevy Is Ready");
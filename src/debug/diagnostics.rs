@@ -8,6 +8,8 @@ use bevy::{
 };
 use bevy_console::ConsoleSet;
 
+use crate::input::Action;
+
 #[derive(Component)]
 struct DiagnosticsDisplay;
 
@@ -37,9 +39,9 @@ pub(super) fn plugin(app: &mut App) {
     // DiagnosticsPlugin::default() is added by default in main.rs
     app.init_state::<DiagnosticsState>();
     app.add_plugins(FrameTimeDiagnosticsPlugin::default())
-        .add_plugins(EntityCountDiagnosticsPlugin::default())
-        .add_plugins(SystemInformationDiagnosticsPlugin::default())
-        .add_plugins(RenderDiagnosticsPlugin::default());
+        .add_plugins(EntityCountDiagnosticsPlugin)
+        .add_plugins(SystemInformationDiagnosticsPlugin)
+        .add_plugins(RenderDiagnosticsPlugin);
 
     // add a way to print it to the screen
     app.add_systems(
@@ -60,10 +62,10 @@ struct DiagnosticsUi;
 impl DiagnosticsUi {
     pub fn toggle(
         state: Res<State<DiagnosticsState>>,
-        input: Res<ButtonInput<KeyCode>>,
+        input: Res<ButtonInput<Action>>,
         mut next_game_state: ResMut<NextState<DiagnosticsState>>,
     ) {
-        if input.just_pressed(KeyCode::F2) {
+        if input.just_pressed(Action::ToggleDiagnostics) {
             let new_state = if *state == DiagnosticsState::Enabled {
                 DiagnosticsState::Disabled
             } else {
@@ -161,23 +163,20 @@ impl DiagnosticsUi {
         >,
     ) {
         // Update FPS
-        if let Ok(mut text) = fps_query.single_mut() {
-            if let Some(fps_diagnostic) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS) {
-                if let Some(fps_smoothed) = fps_diagnostic.smoothed() {
-                    **text = format!("FPS: {:.1}", fps_smoothed);
-                }
-            }
+        if let Ok(mut text) = fps_query.single_mut()
+            && let Some(fps_diagnostic) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS)
+            && let Some(fps_smoothed) = fps_diagnostic.smoothed()
+        {
+            **text = format!("FPS: {:.1}", fps_smoothed);
         }
 
         // Update Entity Count
-        if let Ok(mut text) = entity_query.single_mut() {
-            if let Some(entity_diagnostic) =
+        if let Ok(mut text) = entity_query.single_mut()
+            && let Some(entity_diagnostic) =
                 diagnostics.get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
-            {
-                if let Some(entity_count) = entity_diagnostic.smoothed() {
-                    **text = format!("Entities: {:.0}", entity_count);
-                }
-            }
+            && let Some(entity_count) = entity_diagnostic.smoothed()
+        {
+            **text = format!("Entities: {:.0}", entity_count);
         }
 
         // Update Memory Usage (from system diagnostics)
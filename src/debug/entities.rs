@@ -0,0 +1,468 @@
+//! Console commands to find entities and look at the hierarchy, along with
+//! autocompletion of entity and component names for all entity commands.
+//!
+//! Entities can be given by their ID (e.g. `12v1`), their [`Name`], or a
+//! component they have (e.g. `Level`).
+
+use std::collections::BTreeSet;
+
+use bevy::{
+    ecs::{
+        component::{ComponentId, Components},
+        system::SystemParam,
+    },
+    prelude::*,
+    reflect::TypeRegistry,
+};
+use bevy_console::{
+    AddConsoleCommand, ConsoleCommand, ConsoleConfiguration, ConsoleOpen, ConsoleSet, reply,
+};
+use clap::Parser;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        update_completions
+            .run_if(resource_changed::<ConsoleOpen>)
+            .before(ConsoleSet::ConsoleUI),
+    );
+
+    app.add_console_command::<LsCommand, _>(LsCommand::driver);
+    app.add_console_command::<FindCommand, _>(FindCommand::driver);
+}
+
+/// Looks up and describes entities for the console.
+#[derive(SystemParam)]
+pub(super) struct EntityDescriber<'w, 's> {
+    pub(super) entity_query: Query<'w, 's, EntityRef<'static>>,
+    components: &'w Components,
+    pub(super) type_registry: Res<'w, AppTypeRegistry>,
+}
+
+impl EntityDescriber<'_, '_> {
+    /// Find a component by its short or full type path.
+    fn component_id(&self, registry: &TypeRegistry, name: &str) -> Option<ComponentId> {
+        let registration = registry
+            .get_with_short_type_path(name)
+            .or_else(|| registry.get_with_type_path(name))?;
+        self.components.get_id(registration.type_id())
+    }
+
+    /// The entities with an ID, a name or a component.
+    pub(super) fn resolve(&self, target: &str) -> Vec<Entity> {
+        let component_id = self.component_id(&self.type_registry.read(), target);
+        let mut entities = self
+            .entity_query
+            .iter()
+            .filter(|entity| {
+                entity.id().to_string() == target
+                    || entity
+                        .get::<Name>()
+                        .is_some_and(|name| name.as_str() == target)
+                    || component_id.is_some_and(|id| entity.contains_id(id))
+            })
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+        entities.sort();
+        entities
+    }
+
+    /// The names of all components of an entity, sorted.
+    fn component_names(&self, registry: &TypeRegistry, entity: EntityRef) -> Vec<String> {
+        let mut names = entity
+            .archetype()
+            .components()
+            .filter_map(|id| self.components.get_info(id))
+            .map(|info| {
+                info.type_id()
+                    .and_then(|type_id| registry.get(type_id))
+                    .map_or_else(
+                        || info.name().to_string(),
+                        |registration| {
+                            registration
+                                .type_info()
+                                .type_path_table()
+                                .short_path()
+                                .to_string()
+                        },
+                    )
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// A line with the ID and name of an entity, and optionally its components.
+    fn describe(&self, entity: EntityRef, components: bool) -> String {
+        let mut line = entity.id().to_string();
+        if let Some(name) = entity.get::<Name>() {
+            line += &format!(" {name}");
+        }
+        if let Some(children) = entity.get::<Children>() {
+            line += &format!(" ({} children)", children.len());
+        }
+        if components {
+            let names = self.component_names(&self.type_registry.read(), entity);
+            line += &format!(": {}", names.join(", "));
+        }
+        line
+    }
+
+    /// Describe an entity and its descendants, indented by their depth.
+    fn tree(
+        &self,
+        entity: Entity,
+        depth: usize,
+        max_depth: Option<usize>,
+        components: bool,
+        lines: &mut Vec<String>,
+    ) {
+        let Ok(entity_ref) = self.entity_query.get(entity) else {
+            return;
+        };
+        lines.push(format!(
+            "{}{}",
+            "  ".repeat(depth),
+            self.describe(entity_ref, components)
+        ));
+        if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return;
+        }
+        if let Some(children) = entity_ref.get::<Children>() {
+            for &child in &children[..] {
+                self.tree(child, depth + 1, max_depth, components, lines);
+            }
+        }
+    }
+}
+
+/// Prints the hierarchy below entities, or lists all root entities
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "ls")]
+struct LsCommand {
+    /// The ID, name or a component of the entities, e.g. Level
+    target: Option<String>,
+    /// How many levels of children to print
+    #[arg(short, long)]
+    depth: Option<usize>,
+    /// Print the components of every entity
+    #[arg(short, long)]
+    components: bool,
+}
+
+impl LsCommand {
+    fn driver(mut log: ConsoleCommand<LsCommand>, describer: EntityDescriber) {
+        if let Some(Ok(command)) = log.take() {
+            match command.run(&describer) {
+                Ok(lines) => {
+                    for line in lines {
+                        reply!(log, "{line}");
+                    }
+                }
+                Err(error) => reply!(log, "{error}"),
+            }
+        }
+    }
+
+    fn run(&self, describer: &EntityDescriber) -> Result<Vec<String>, BevyError> {
+        let (entities, depth) = match &self.target {
+            Some(target) => (describer.resolve(target), self.depth),
+            None => {
+                let mut roots = describer
+                    .entity_query
+                    .iter()
+                    .filter(|entity| !entity.contains::<ChildOf>())
+                    .map(|entity| entity.id())
+                    .collect::<Vec<_>>();
+                roots.sort();
+                (roots, Some(self.depth.unwrap_or(0)))
+            }
+        };
+        if entities.is_empty() {
+            return Err(format!(
+                "No entity matches {}",
+                self.target.as_deref().unwrap_or_default()
+            )
+            .into());
+        }
+
+        let mut lines = Vec::new();
+        for entity in entities {
+            describer.tree(entity, 0, depth, self.components, &mut lines);
+        }
+        Ok(lines)
+    }
+}
+
+/// Lists entities with their components by name, component or parent
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "find")]
+struct FindCommand {
+    /// A part of the name of the entities, ignoring case
+    name: Option<String>,
+    /// A component the entities have, e.g. MovementController
+    #[arg(short, long)]
+    with: Vec<String>,
+    /// The ID, name or a component of the parent of the entities
+    #[arg(short, long)]
+    parent: Option<String>,
+}
+
+impl FindCommand {
+    fn driver(mut log: ConsoleCommand<FindCommand>, describer: EntityDescriber) {
+        if let Some(Ok(command)) = log.take() {
+            match command.run(&describer) {
+                Ok(lines) => {
+                    for line in lines {
+                        reply!(log, "{line}");
+                    }
+                }
+                Err(error) => reply!(log, "{error}"),
+            }
+        }
+    }
+
+    fn run(&self, describer: &EntityDescriber) -> Result<Vec<String>, BevyError> {
+        let mut component_ids = Vec::new();
+        for component in &self.with {
+            let id = describer
+                .component_id(&describer.type_registry.read(), component)
+                .ok_or_else(|| format!("Unknown component {component}"))?;
+            component_ids.push(id);
+        }
+        let parents = self
+            .parent
+            .as_deref()
+            .map(|parent| describer.resolve(parent));
+        let name = self.name.as_ref().map(|name| name.to_lowercase());
+
+        let mut entities = describer
+            .entity_query
+            .iter()
+            .filter(|entity| {
+                name.as_ref().is_none_or(|name| {
+                    entity
+                        .get::<Name>()
+                        .is_some_and(|entity_name| entity_name.to_lowercase().contains(name))
+                })
+            })
+            .filter(|entity| component_ids.iter().all(|&id| entity.contains_id(id)))
+            .filter(|entity| {
+                parents.as_ref().is_none_or(|parents| {
+                    entity
+                        .get::<ChildOf>()
+                        .is_some_and(|child_of| parents.contains(&child_of.parent()))
+                })
+            })
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|entity| entity.id());
+
+        if entities.is_empty() {
+            return Err("No entities found".into());
+        }
+        Ok(entities
+            .into_iter()
+            .map(|entity| describer.describe(entity, true))
+            .collect())
+    }
+}
+
+/// Offer the names of entities and components as completions whenever the
+/// console is opened.
+fn update_completions(
+    console_open: Res<ConsoleOpen>,
+    mut config: ResMut<ConsoleConfiguration>,
+    describer: EntityDescriber,
+) {
+    if console_open.open {
+        config.arg_completions = completions(&describer);
+    }
+}
+
+/// Completions for the entity commands, with the names of entities and of the
+/// reflected components, which are the only ones the commands can work with.
+fn completions(describer: &EntityDescriber) -> Vec<Vec<String>> {
+    let registry = describer.type_registry.read();
+    let mut completions = BTreeSet::new();
+
+    for entity in &describer.entity_query {
+        let Some(name) = entity.get::<Name>() else {
+            continue;
+        };
+        let name = quote(name.as_str());
+        for command in ["ls", "find", "get", "set", "insert", "remove"] {
+            completions.insert(vec![command.to_string(), name.clone()]);
+        }
+        for registration in entity
+            .archetype()
+            .components()
+            .filter_map(|id| describer.components.get_info(id)?.type_id())
+            .filter_map(|type_id| registry.get(type_id))
+            .filter(|registration| registration.data::<ReflectComponent>().is_some())
+        {
+            let component = registration.type_info().type_path_table().short_path();
+            for command in ["get", "set", "remove"] {
+                completions.insert(vec![
+                    command.to_string(),
+                    name.clone(),
+                    component.to_string(),
+                ]);
+            }
+        }
+    }
+
+    for registration in registry.iter() {
+        if registration.data::<ReflectComponent>().is_none() {
+            continue;
+        }
+        let component = registration.type_info().type_path_table().short_path();
+        completions.insert(vec!["ls".to_string(), component.to_string()]);
+        completions.insert(vec![
+            "find".to_string(),
+            "--with".to_string(),
+            component.to_string(),
+        ]);
+    }
+
+    completions.into_iter().collect()
+}
+
+/// Quote a name with spaces, so the console reads it as a single argument.
+fn quote(name: &str) -> String {
+    shlex::try_quote(name).map_or_else(|_| name.to_string(), |quoted| quoted.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Level;
+
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Speed;
+
+    /// Registered for reflection, but not as a component.
+    #[derive(Component, Reflect)]
+    struct Secret;
+
+    /// A world with a level holding a player and a wall, and a camera.
+    fn world() -> (World, [Entity; 4]) {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        {
+            let mut registry = world.resource::<AppTypeRegistry>().write();
+            registry.register::<Level>();
+            registry.register::<Speed>();
+            registry.register::<Secret>();
+        }
+        let level = world.spawn((Name::new("Level"), Level)).id();
+        let player = world
+            .spawn((Name::new("Player One"), Speed, Secret, ChildOf(level)))
+            .id();
+        let wall = world.spawn((Name::new("Wall"), ChildOf(level))).id();
+        let camera = world.spawn(Name::new("Camera")).id();
+        (world, [level, player, wall, camera])
+    }
+
+    fn ls(world: &mut World, command: &str) -> Result<Vec<String>, BevyError> {
+        let command = LsCommand::try_parse_from(command.split(' ')).unwrap();
+        world
+            .run_system_once_with(
+                |In(command): In<LsCommand>, describer: EntityDescriber| command.run(&describer),
+                command,
+            )
+            .unwrap()
+    }
+
+    fn find(world: &mut World, command: &str) -> Result<Vec<String>, BevyError> {
+        let command = FindCommand::try_parse_from(command.split(' ')).unwrap();
+        world
+            .run_system_once_with(
+                |In(command): In<FindCommand>, describer: EntityDescriber| command.run(&describer),
+                command,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn ls_lists_the_roots_without_a_target() {
+        let (mut world, [level, _, _, camera]) = world();
+
+        assert_eq!(
+            ls(&mut world, "ls").unwrap(),
+            [
+                format!("{level} Level (2 children)"),
+                format!("{camera} Camera")
+            ]
+        );
+    }
+
+    #[test]
+    fn ls_prints_the_hierarchy_below_a_target() {
+        let (mut world, [level, player, wall, _]) = world();
+        let lines = [
+            format!("{level} Level (2 children)"),
+            format!("  {player} Player One"),
+            format!("  {wall} Wall"),
+        ];
+
+        assert_eq!(ls(&mut world, "ls Level").unwrap(), lines);
+        assert_eq!(ls(&mut world, &format!("ls {level}")).unwrap(), lines);
+        assert_eq!(ls(&mut world, "ls --depth 0 Level").unwrap(), &lines[..1]);
+        assert!(ls(&mut world, "ls Nobody").is_err());
+    }
+
+    #[test]
+    fn find_filters_by_name_component_and_parent() {
+        let (mut world, [_, player, wall, _]) = world();
+        let starts_with = |lines: Vec<String>, entities: &[Entity]| {
+            lines.len() == entities.len()
+                && lines
+                    .iter()
+                    .zip(entities)
+                    .all(|(line, entity)| line.starts_with(&format!("{entity} ")))
+        };
+
+        assert!(starts_with(
+            find(&mut world, "find player").unwrap(),
+            &[player]
+        ));
+        assert!(starts_with(
+            find(&mut world, "find --with Speed").unwrap(),
+            &[player]
+        ));
+        assert!(starts_with(
+            find(&mut world, "find --parent Level").unwrap(),
+            &[player, wall]
+        ));
+        assert!(starts_with(
+            find(&mut world, "find wa --parent Level").unwrap(),
+            &[wall]
+        ));
+        assert!(find(&mut world, "find --with Unknown").is_err());
+        assert!(find(&mut world, "find nobody").is_err());
+    }
+
+    #[test]
+    fn completions_offer_only_reflected_components() {
+        let (mut world, _) = world();
+        let completions = world
+            .run_system_once(|describer: EntityDescriber| completions(&describer))
+            .unwrap();
+        let has = |completion: &[&str]| completions.iter().any(|c| c == completion);
+
+        assert!(has(&["get", "'Player One'", "Speed"]));
+        assert!(has(&["remove", "'Player One'", "Speed"]));
+        assert!(has(&["insert", "Wall"]));
+        assert!(has(&["ls", "Level"]));
+        assert!(has(&["find", "--with", "Speed"]));
+        assert!(!has(&["get", "'Player One'", "Secret"]));
+        assert!(!has(&["get", "'Player One'", "Name"]));
+        assert!(!has(&["find", "--with", "Secret"]));
+    }
+}
@@ -0,0 +1,300 @@
+//! Writes every log line, console command and console reply to a file, so
+//! playtesters can attach the log of a session to bug reports.
+//!
+//! Each session gets its own `session-<timestamp>-<part>.log` files in the
+//! `logs` directory next to the saves, with the timestamp in milliseconds. A
+//! new part is started whenever the current one grows too big, only the last
+//! few parts of a session are kept, and only the last few sessions are kept.
+//! The file is flushed every frame and when the game panics.
+//!
+//! Insert a [`LogDir`] before adding the [`LogPlugin`](bevy::log::LogPlugin)
+//! to write the logs somewhere else, or nowhere at all.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    log::{
+        BoxedLayer,
+        tracing_subscriber::{Layer, layer::Context},
+    },
+    prelude::*,
+};
+use bevy_console::{ConsoleCommandEntered, PrintConsoleLine};
+use tracing::{Event, Subscriber};
+
+use crate::debug::logging::{CapturedLogs, FieldVisitor};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Last,
+        (record_console, flush_log_file)
+            .chain()
+            .run_if(resource_exists::<SessionLog>),
+    );
+}
+
+/// A part is closed once it grows beyond this many bytes.
+const MAX_PART_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Older parts of the current session are deleted beyond this many.
+const MAX_PARTS: u32 = 4;
+
+/// Older sessions are deleted beyond this many, including the current one.
+const MAX_SESSIONS: usize = 10;
+
+/// The directory that log files are written to, if any.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct LogDir(pub Option<PathBuf>);
+
+impl Default for LogDir {
+    /// The `logs` directory in the user data directory, if the platform has one.
+    fn default() -> Self {
+        Self(dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("logs")))
+    }
+}
+
+/// The log file of the current session, split into parts.
+pub struct LogFile {
+    dir: PathBuf,
+    /// When the session started, in milliseconds since the Unix epoch.
+    session: u64,
+    part: u32,
+    size: u64,
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl LogFile {
+    /// Start a new session in `dir`, deleting the oldest sessions.
+    pub fn create(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let mut session = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        delete_old_sessions(&dir)?;
+        // Another instance of the game may have started in the same millisecond.
+        let file = loop {
+            match File::create_new(dir.join(part_name(session, 0))) {
+                Ok(file) => break file,
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => session += 1,
+                Err(error) => return Err(error),
+            }
+        };
+        let writer = BufWriter::new(file);
+        Ok(Self {
+            dir,
+            session,
+            part: 0,
+            size: 0,
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Write a line, prefixed with the time since the session started.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let line = format!("[{:>10.3}] {line}\n", self.start.elapsed().as_secs_f64());
+        if self.size > 0 && self.size + line.len() as u64 > MAX_PART_SIZE {
+            self.start_next_part()?;
+        }
+        self.writer.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn start_next_part(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.part += 1;
+        self.size = 0;
+        self.writer = BufWriter::new(File::create(
+            self.dir.join(part_name(self.session, self.part)),
+        )?);
+        if let Some(old_part) = self.part.checked_sub(MAX_PARTS) {
+            let old_path = self.dir.join(part_name(self.session, old_part));
+            fs::remove_file(old_path).or_else(|error| match error.kind() {
+                // Someone else cleaned up already.
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(error),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn part_name(session: u64, part: u32) -> String {
+    format!("session-{session}-{part}.log")
+}
+
+/// The session that a log file belongs to, if it is one.
+fn session_of(file_name: &str) -> Option<u64> {
+    let (session, _part) = file_name
+        .strip_prefix("session-")?
+        .strip_suffix(".log")?
+        .split_once('-')?;
+    session.parse().ok()
+}
+
+/// Make room for a new session.
+fn delete_old_sessions(dir: &Path) -> io::Result<()> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if let Some(session) = entry.file_name().to_str().and_then(session_of) {
+            files.push((session, entry.path()));
+        }
+    }
+    let mut sessions = files
+        .iter()
+        .map(|(session, _)| *session)
+        .collect::<Vec<_>>();
+    sessions.sort_unstable();
+    sessions.dedup();
+
+    let keep = MAX_SESSIONS.saturating_sub(1);
+    let Some(&oldest_kept) = sessions.iter().rev().take(keep).next_back() else {
+        return Ok(());
+    };
+    for (session, path) in files {
+        if session < oldest_kept {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The log file of the current session, shared with the log layer and the
+/// panic hook.
+#[derive(Resource, Clone)]
+struct SessionLog(Arc<Mutex<LogFile>>);
+
+/// The log file that panics are written to, which is the latest session's.
+static PANIC_LOG: Mutex<Option<SessionLog>> = Mutex::new(None);
+
+/// The panic hook is only installed once, even if several apps are created.
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+/// Write panics to `session_log`, after the previous panic hook.
+fn log_panics_to(session_log: SessionLog) {
+    if let Ok(mut panic_log) = PANIC_LOG.lock() {
+        *panic_log = Some(session_log);
+    }
+    INSTALL_PANIC_HOOK.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // The panic may have happened while a lock was held.
+            let panic_log = PANIC_LOG.try_lock();
+            let session_log = panic_log.as_ref().ok().and_then(|log| log.as_ref());
+            if let Some(Ok(mut file)) = session_log.map(|log| log.0.try_lock()) {
+                let _ = file.write_line(&format!("panic: {info}"));
+                let _ = file.flush();
+            }
+            previous_hook(info);
+        }));
+    });
+}
+
+/// Creates the layer that writes all logs to the session's log file, if there
+/// is a [`LogDir`].
+pub fn file_layer(app: &mut App) -> Option<BoxedLayer> {
+    let dir = app
+        .world_mut()
+        .get_resource_or_insert_with(LogDir::default)
+        .0
+        .clone()?;
+    let file = match LogFile::create(dir.clone()) {
+        Ok(file) => file,
+        Err(error) => {
+            // The logger isn't set up yet.
+            eprintln!("Failed to create a log file in {}: {error}", dir.display());
+            return None;
+        }
+    };
+    let session_log = SessionLog(Arc::new(Mutex::new(file)));
+    app.insert_resource(session_log.clone());
+
+    // Don't lose the last lines before a crash.
+    log_panics_to(session_log.clone());
+
+    Some(FileLayer(session_log).boxed())
+}
+
+struct FileLayer(SessionLog);
+
+impl<S: Subscriber> Layer<S> for FileLayer {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = format!("{:>5} {}: ", metadata.level(), metadata.target());
+        event.record(&mut FieldVisitor(&mut line));
+        if let Ok(mut file) = self.0.0.lock() {
+            let _ = file.write_line(&line);
+        }
+    }
+}
+
+fn record_console(
+    session_log: Res<SessionLog>,
+    captured_logs: Option<Res<CapturedLogs>>,
+    mut commands: EventReader<ConsoleCommandEntered>,
+    mut lines: EventReader<PrintConsoleLine>,
+) {
+    let Ok(mut file) = session_log.0.lock() else {
+        return;
+    };
+    for ConsoleCommandEntered { command_name, args } in commands.read() {
+        let _ = file.write_line(&format!("> {command_name} {}", args.join(" ")));
+    }
+    let mut forwarded = captured_logs
+        .as_ref()
+        .map(|logs| logs.forwarded.iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+    for PrintConsoleLine { line } in lines.read() {
+        // Logs shown in the console have been written already.
+        if let Some(index) = forwarded.iter().position(|&forwarded| forwarded == line) {
+            forwarded.swap_remove(index);
+            continue;
+        }
+        let _ = file.write_line(&format!("< {line}"));
+    }
+}
+
+fn flush_log_file(session_log: Res<SessionLog>) {
+    if let Ok(mut file) = session_log.0.lock() {
+        let _ = file.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_started_at_once_get_their_own_files() {
+        let dir = std::env::temp_dir().join(format!("template_2d_logs_{}", std::process::id()));
+        let first = LogFile::create(dir.clone()).unwrap();
+        let second = LogFile::create(dir.clone()).unwrap();
+        assert_ne!(first.session, second.session);
+
+        drop((first, second));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_the_session_from_file_names() {
+        assert_eq!(
+            session_of("session-1700000000123-2.log"),
+            Some(1_700_000_000_123)
+        );
+        assert_eq!(session_of("settings.ron"), None);
+    }
+}
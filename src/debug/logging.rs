@@ -0,0 +1,166 @@
+//! Shows `tracing` logs in the in-game console, colour-coded by level.
+//!
+//! The console has its own [`EnvFilter`], which the `loglevel` command changes
+//! at runtime. It can only narrow down what the global filter of the
+//! [`LogPlugin`](bevy::log::LogPlugin) lets through, which is `info` and above
+//! by default and can be widened with the `RUST_LOG` environment variable.
+
+use std::{
+    fmt::{self, Write},
+    sync::{
+        Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+use bevy::{
+    log::{
+        BoxedLayer,
+        tracing_subscriber::{EnvFilter, Layer, Registry, layer::Context, reload},
+    },
+    prelude::*,
+};
+use bevy_console::{AddConsoleCommand, ConsoleCommand, ConsoleSet, reply};
+use clap::Parser;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+
+use crate::debug::{console::Console, log_file};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        forward_logs_to_console
+            .run_if(resource_exists::<CapturedLogs>)
+            .after(ConsoleSet::ConsoleUI),
+    );
+    app.add_console_command::<LogLevelCommand, _>(LogLevelCommand::driver);
+}
+
+/// What the console shows until it's changed with the `loglevel` command.
+const DEFAULT_FILTER: &str = "warn,template_2d=info";
+
+/// Creates the layers that capture logs for the console and the log file.
+/// Pass this to [`LogPlugin::custom_layer`](bevy::log::LogPlugin::custom_layer).
+pub fn custom_layer(app: &mut App) -> Option<BoxedLayer> {
+    let layers = [console_layer(app), log_file::file_layer(app)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    Some(Box::new(layers))
+}
+
+fn console_layer(app: &mut App) -> Option<BoxedLayer> {
+    let (sender, receiver) = mpsc::channel();
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(DEFAULT_FILTER));
+    app.insert_resource(CapturedLogs {
+        receiver: Mutex::new(receiver),
+        forwarded: Vec::new(),
+    });
+    app.insert_resource(ConsoleLogFilter(handle));
+    Some(ConsoleLayer { sender }.with_filter(filter).boxed())
+}
+
+/// Log lines waiting to be printed to the console.
+#[derive(Resource)]
+pub(super) struct CapturedLogs {
+    receiver: Mutex<Receiver<String>>,
+    /// The lines printed to the console this frame, so the log file can tell
+    /// them apart from command replies.
+    pub(super) forwarded: Vec<String>,
+}
+
+/// Changes which logs are shown in the console.
+#[derive(Resource)]
+struct ConsoleLogFilter(reload::Handle<EnvFilter, Registry>);
+
+struct ConsoleLayer {
+    sender: Sender<String>,
+}
+
+impl<S: Subscriber> Layer<S> for ConsoleLayer {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+        let color = match *metadata.level() {
+            Level::ERROR => "31",
+            Level::WARN => "33",
+            Level::INFO => "32",
+            Level::DEBUG => "34",
+            Level::TRACE => "35",
+        };
+        let mut line = format!(
+            "\x1b[{color}m{:>5}\x1b[0m {}: ",
+            metadata.level(),
+            metadata.target()
+        );
+        event.record(&mut FieldVisitor(&mut line));
+        // The console may be gone already while the app shuts down.
+        let _ = self.sender.send(line);
+    }
+}
+
+/// Writes the message of an event, followed by its other fields.
+pub(super) struct FieldVisitor<'a>(pub &'a mut String);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = if field.name() == "message" {
+            write!(self.0, "{value:?}")
+        } else {
+            write!(self.0, " {}={value:?}", field.name())
+        };
+    }
+}
+
+fn forward_logs_to_console(mut logs: ResMut<CapturedLogs>, mut console: Console) {
+    let CapturedLogs {
+        receiver,
+        forwarded,
+    } = &mut *logs;
+    forwarded.clear();
+    let Ok(receiver) = receiver.get_mut() else {
+        return;
+    };
+    for line in receiver.try_iter() {
+        forwarded.push(line.clone());
+        console.log(line);
+    }
+}
+
+/// Shows or changes which logs are printed to the console
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "loglevel")]
+struct LogLevelCommand {
+    /// A filter such as `debug` or `warn,template_2d::demo=trace`
+    filter: Option<String>,
+}
+
+impl LogLevelCommand {
+    fn driver(
+        mut log: ConsoleCommand<LogLevelCommand>,
+        console_filter: Option<Res<ConsoleLogFilter>>,
+    ) {
+        if let Some(Ok(LogLevelCommand { filter })) = log.take() {
+            let Some(console_filter) = console_filter else {
+                reply!(log, "Logs are not captured for the console");
+                return;
+            };
+            let Some(filter) = filter else {
+                match console_filter.0.with_current(ToString::to_string) {
+                    Ok(filter) => reply!(log, "Log filter: {filter}"),
+                    Err(error) => reply!(log, "Failed to read the log filter: {error}"),
+                }
+                return;
+            };
+            match EnvFilter::try_new(&filter) {
+                Ok(new_filter) => match console_filter.0.reload(new_filter) {
+                    Ok(()) => reply!(log, "Log filter set to {filter}"),
+                    Err(error) => reply!(log, "Failed to change the log filter: {error}"),
+                },
+                Err(error) => reply!(log, "Invalid log filter {filter:?}: {error}"),
+            }
+        }
+    }
+}
@@ -2,6 +2,10 @@ use bevy::prelude::*;
 
 pub mod console;
 pub mod diagnostics;
+pub mod entities;
+pub mod log_file;
+pub mod logging;
+pub mod reflection;
 
 #[cfg(feature = "dev")]
 pub mod dev_only;
@@ -9,6 +13,10 @@ pub mod dev_only;
 pub fn plugin(app: &mut App) {
     app.add_plugins(console::plugin);
     app.add_plugins(diagnostics::plugin);
+    app.add_plugins(entities::plugin);
+    app.add_plugins(log_file::plugin);
+    app.add_plugins(logging::plugin);
+    app.add_plugins(reflection::plugin);
     #[cfg(feature = "dev")]
     app.add_plugins(dev_only::plugin);
 }
@@ -0,0 +1,546 @@
+//! Console commands to read and change components through reflection, so values
+//! can be tuned while the game is running.
+//!
+//! Entities are given by their ID, their [`Name`] or a component they have, like
+//! for the commands in [`entities`](super::entities). Components are given by
+//! their type name, optionally followed by a path to a field. Values are written
+//! in RON, like in `*.ron` assets:
+//!
+//! ```text
+//! get Player MovementController.max_speed
+//! set Player MovementController.max_speed 800
+//! insert Player ScreenWrap (mode: Wrap, margin: 0.0, arena: Window)
+//! remove Player ScreenWrap
+//! ```
+
+use bevy::{
+    ecs::reflect::ReflectCommandExt,
+    prelude::*,
+    reflect::{
+        GetPath, PartialReflect, ReflectFromReflect, TypeRegistration, TypeRegistry,
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+    },
+};
+use bevy_console::{AddConsoleCommand, ConsoleCommand, reply};
+use clap::Parser;
+use serde::de::DeserializeSeed;
+
+use super::entities::EntityDescriber;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_console_command::<GetCommand, _>(GetCommand::driver);
+    app.add_console_command::<SetCommand, _>(SetCommand::driver);
+    app.add_console_command::<InsertCommand, _>(InsertCommand::driver);
+    app.add_console_command::<RemoveCommand, _>(RemoveCommand::driver);
+}
+
+/// Split `Component.field` into the component and the path to the field, which
+/// is empty for the whole component.
+fn split_path(path: &str) -> (&str, &str) {
+    path.find(['.', '['])
+        .map_or((path, ""), |index| path.split_at(index))
+}
+
+/// Find a reflected component by its short or full type path.
+fn component_registration<'a>(
+    registry: &'a TypeRegistry,
+    name: &str,
+) -> Result<(&'a TypeRegistration, &'a ReflectComponent), BevyError> {
+    let registration = registry
+        .get_with_short_type_path(name)
+        .or_else(|| registry.get_with_type_path(name))
+        .ok_or_else(|| format!("Unknown type {name}"))?;
+    let reflect_component = registration
+        .data::<ReflectComponent>()
+        .ok_or_else(|| format!("{name} is not a reflected component"))?;
+    Ok((registration, reflect_component))
+}
+
+/// A field of a component, or the whole component for an empty path.
+fn field<'a>(component: &'a dyn Reflect, path: &str) -> Result<&'a dyn PartialReflect, BevyError> {
+    if path.is_empty() {
+        return Ok(component.as_partial_reflect());
+    }
+    Ok(component
+        .reflect_path(path)
+        .map_err(|error| error.to_string())?)
+}
+
+fn field_mut<'a>(
+    component: &'a mut dyn Reflect,
+    path: &str,
+) -> Result<&'a mut dyn PartialReflect, BevyError> {
+    if path.is_empty() {
+        return Ok(component.as_partial_reflect_mut());
+    }
+    Ok(component
+        .reflect_path_mut(path)
+        .map_err(|error| error.to_string())?)
+}
+
+/// The name and ID of an entity, for replies.
+fn label(entity: EntityRef) -> String {
+    match entity.get::<Name>() {
+        Some(name) => format!("{name} {}", entity.id()),
+        None => entity.id().to_string(),
+    }
+}
+
+fn to_ron(value: &dyn PartialReflect, registry: &TypeRegistry) -> String {
+    ron::to_string(&TypedReflectSerializer::new(value, registry))
+        .unwrap_or_else(|_| format!("{value:?}"))
+}
+
+fn from_ron(
+    registration: &TypeRegistration,
+    registry: &TypeRegistry,
+    ron: &str,
+) -> Result<Box<dyn PartialReflect>, BevyError> {
+    let mut deserializer = ron::Deserializer::from_str(ron)?;
+    let value =
+        TypedReflectDeserializer::new(registration, registry).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Prints a component, or one of its fields, of entities
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "get")]
+struct GetCommand {
+    /// The ID, name or a component of the entities, e.g. Player
+    target: String,
+    /// The component and an optional field, e.g. MovementController.max_speed
+    path: String,
+}
+
+impl GetCommand {
+    fn driver(mut log: ConsoleCommand<GetCommand>, describer: EntityDescriber) {
+        if let Some(Ok(command)) = log.take() {
+            match command.run(&describer) {
+                Ok(lines) => {
+                    for line in lines {
+                        reply!(log, "{line}");
+                    }
+                }
+                Err(error) => reply!(log, "{error}"),
+            }
+        }
+    }
+
+    fn run(&self, describer: &EntityDescriber) -> Result<Vec<String>, BevyError> {
+        let (component_name, field_path) = split_path(&self.path);
+        let registry = describer.type_registry.read();
+        let (_, reflect_component) = component_registration(&registry, component_name)?;
+
+        let mut lines = Vec::new();
+        for entity in describer
+            .entity_query
+            .iter_many(describer.resolve(&self.target))
+        {
+            let component = reflect_component
+                .reflect(entity)
+                .ok_or_else(|| format!("{} has no {component_name}", label(entity)))?;
+            let value = field(component, field_path)?;
+            lines.push(format!("{}: {}", label(entity), to_ron(value, &registry)));
+        }
+        if lines.is_empty() {
+            return Err(format!("No entity matches {}", self.target).into());
+        }
+        Ok(lines)
+    }
+}
+
+/// Changes a component, or one of its fields, of entities
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "set")]
+struct SetCommand {
+    /// The ID, name or a component of the entities, e.g. Player
+    target: String,
+    /// The component and an optional field, e.g. MovementController.max_speed
+    path: String,
+    /// The new value in RON, e.g. 800 or (x: 1, y: 2)
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    value: Vec<String>,
+}
+
+impl SetCommand {
+    fn driver(
+        mut log: ConsoleCommand<SetCommand>,
+        mut entities: ParamSet<(EntityDescriber, Query<EntityMut<'static>>)>,
+    ) {
+        if let Some(Ok(command)) = log.take() {
+            match command.run(&mut entities) {
+                Ok(lines) => {
+                    for line in lines {
+                        reply!(log, "{line}");
+                    }
+                }
+                Err(error) => reply!(log, "{error}"),
+            }
+        }
+    }
+
+    /// Parse the value and check every entity before changing any of them, so a
+    /// mistake neither changes some of the entities nor triggers change detection.
+    fn run(
+        &self,
+        entities: &mut ParamSet<(EntityDescriber, Query<EntityMut<'static>>)>,
+    ) -> Result<Vec<String>, BevyError> {
+        let (component_name, field_path) = split_path(&self.path);
+        let describer = entities.p0();
+        let type_registry = describer.type_registry.clone();
+        let registry = type_registry.read();
+        let (_, reflect_component) = component_registration(&registry, component_name)?;
+
+        let mut targets = Vec::new();
+        let mut value = None;
+        for entity in describer
+            .entity_query
+            .iter_many(describer.resolve(&self.target))
+        {
+            let label = label(entity);
+            let component = reflect_component
+                .reflect(entity)
+                .ok_or_else(|| format!("{label} has no {component_name}"))?;
+            let registration = field(component, field_path)?
+                .get_represented_type_info()
+                .and_then(|type_info| registry.get(type_info.type_id()))
+                .ok_or_else(|| format!("The type of {} is not registered", self.path))?;
+            if value.is_none() {
+                value = Some(from_ron(registration, &registry, &self.value.join(" "))?);
+            }
+            targets.push((entity.id(), label));
+        }
+        let Some(value) = value else {
+            return Err(format!("No entity matches {}", self.target).into());
+        };
+
+        let mut entity_query = entities.p1();
+        let mut lines = Vec::new();
+        for (entity, label) in targets {
+            let mut component = reflect_component
+                .reflect_mut(entity_query.get_mut(entity)?)
+                .ok_or_else(|| format!("{label} has no {component_name}"))?;
+            let target = field_mut(&mut *component, field_path)?;
+            target.try_apply(value.as_ref())?;
+            lines.push(format!("{label}: {}", to_ron(&*target, &registry)));
+        }
+        Ok(lines)
+    }
+}
+
+/// Inserts a component into entities
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "insert")]
+struct InsertCommand {
+    /// The ID, name or a component of the entities, e.g. Player
+    target: String,
+    /// The component, e.g. ScreenWrap
+    component: String,
+    /// The component in RON, or its default value if left out
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    value: Vec<String>,
+}
+
+impl InsertCommand {
+    fn driver(
+        mut log: ConsoleCommand<InsertCommand>,
+        mut commands: Commands,
+        describer: EntityDescriber,
+    ) {
+        if let Some(Ok(command)) = log.take() {
+            match command.run(&mut commands, &describer) {
+                Ok(lines) => {
+                    for line in lines {
+                        reply!(log, "{line}");
+                    }
+                }
+                Err(error) => reply!(log, "{error}"),
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        commands: &mut Commands,
+        describer: &EntityDescriber,
+    ) -> Result<Vec<String>, BevyError> {
+        let entities = describer.resolve(&self.target);
+        if entities.is_empty() {
+            return Err(format!("No entity matches {}", self.target).into());
+        }
+        let component = self.build(&describer.type_registry.read())?;
+
+        let mut lines = Vec::new();
+        for entity in describer.entity_query.iter_many(entities) {
+            let component = component
+                .reflect_clone()
+                .map_err(|error| format!("{} can't be copied: {error}", self.component))?;
+            commands
+                .entity(entity.id())
+                .insert_reflect(component.into_partial_reflect());
+            lines.push(format!(
+                "Inserted {} into {}",
+                self.component,
+                label(entity)
+            ));
+        }
+        Ok(lines)
+    }
+
+    /// Build the component from its RON or default value.
+    fn build(&self, registry: &TypeRegistry) -> Result<Box<dyn Reflect>, BevyError> {
+        let (registration, _) = component_registration(registry, &self.component)?;
+        if self.value.is_empty() {
+            let reflect_default = registration.data::<ReflectDefault>().ok_or_else(|| {
+                format!("{} has no default value, so it needs one", self.component)
+            })?;
+            return Ok(reflect_default.default());
+        }
+
+        let value = from_ron(registration, registry, &self.value.join(" "))?;
+        let component = registration
+            .data::<ReflectFromReflect>()
+            .and_then(|reflect_from_reflect| reflect_from_reflect.from_reflect(value.as_ref()))
+            .ok_or_else(|| format!("{} can't be built from {value:?}", self.component))?;
+        Ok(component)
+    }
+}
+
+/// Removes a component from entities
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "remove")]
+struct RemoveCommand {
+    /// The ID, name or a component of the entities, e.g. Player
+    target: String,
+    /// The component, e.g. ScreenWrap
+    component: String,
+}
+
+impl RemoveCommand {
+    fn driver(
+        mut log: ConsoleCommand<RemoveCommand>,
+        mut commands: Commands,
+        describer: EntityDescriber,
+    ) {
+        if let Some(Ok(command)) = log.take() {
+            match command.run(&mut commands, &describer) {
+                Ok(lines) => {
+                    for line in lines {
+                        reply!(log, "{line}");
+                    }
+                }
+                Err(error) => reply!(log, "{error}"),
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        commands: &mut Commands,
+        describer: &EntityDescriber,
+    ) -> Result<Vec<String>, BevyError> {
+        let registry = describer.type_registry.read();
+        let (registration, _) = component_registration(&registry, &self.component)?;
+        let type_path = registration.type_info().type_path();
+        let entities = describer.resolve(&self.target);
+        if entities.is_empty() {
+            return Err(format!("No entity matches {}", self.target).into());
+        }
+
+        let mut lines = Vec::new();
+        for entity in describer.entity_query.iter_many(entities) {
+            commands.entity(entity.id()).remove_reflect(type_path);
+            lines.push(format!("Removed {} from {}", self.component, label(entity)));
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, Default)]
+    struct Speed {
+        max: f32,
+    }
+
+    /// A world with two entities named `Mover`, where only the first has a
+    /// [`Speed`] unless `both` is set.
+    fn world(both: bool) -> (World, Entity, Entity) {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Speed>();
+        let first = world.spawn((Name::new("Mover"), Speed { max: 1.0 })).id();
+        let second = world.spawn(Name::new("Mover")).id();
+        if both {
+            world.entity_mut(second).insert(Speed { max: 1.0 });
+        }
+        world.clear_trackers();
+        (world, first, second)
+    }
+
+    fn get(world: &mut World, target: &str, path: &str) -> Result<Vec<String>, BevyError> {
+        let command = GetCommand {
+            target: target.to_string(),
+            path: path.to_string(),
+        };
+        world
+            .run_system_once_with(
+                |In(command): In<GetCommand>, describer: EntityDescriber| command.run(&describer),
+                command,
+            )
+            .unwrap()
+    }
+
+    fn set(
+        world: &mut World,
+        target: &str,
+        path: &str,
+        value: &str,
+    ) -> Result<Vec<String>, BevyError> {
+        let command = SetCommand {
+            target: target.to_string(),
+            path: path.to_string(),
+            value: vec![value.to_string()],
+        };
+        world
+            .run_system_once_with(
+                |In(command): In<SetCommand>,
+                 mut entities: ParamSet<(EntityDescriber, Query<EntityMut<'static>>)>| {
+                    command.run(&mut entities)
+                },
+                command,
+            )
+            .unwrap()
+    }
+
+    fn insert(world: &mut World, target: &str, value: &[&str]) -> Result<Vec<String>, BevyError> {
+        let command = InsertCommand {
+            target: target.to_string(),
+            component: "Speed".to_string(),
+            value: value.iter().map(ToString::to_string).collect(),
+        };
+        world
+            .run_system_once_with(
+                |In(command): In<InsertCommand>,
+                 mut commands: Commands,
+                 describer: EntityDescriber| command.run(&mut commands, &describer),
+                command,
+            )
+            .unwrap()
+    }
+
+    fn remove(world: &mut World, target: &str) -> Result<Vec<String>, BevyError> {
+        let command = RemoveCommand {
+            target: target.to_string(),
+            component: "Speed".to_string(),
+        };
+        world
+            .run_system_once_with(
+                |In(command): In<RemoveCommand>,
+                 mut commands: Commands,
+                 describer: EntityDescriber| command.run(&mut commands, &describer),
+                command,
+            )
+            .unwrap()
+    }
+
+    fn speed(world: &World, entity: Entity) -> (f32, bool) {
+        let speed = world.entity(entity).get_ref::<Speed>().unwrap();
+        (speed.max, speed.is_changed())
+    }
+
+    #[test]
+    fn get_finds_entities_by_id_name_or_component() {
+        let (mut world, first, _) = world(true);
+
+        assert_eq!(get(&mut world, "Mover", "Speed.max").unwrap().len(), 2);
+        assert_eq!(get(&mut world, "Speed", "Speed").unwrap().len(), 2);
+        assert_eq!(
+            get(&mut world, &first.to_string(), "Speed.max").unwrap(),
+            [format!("Mover {first}: 1.0")]
+        );
+        assert!(get(&mut world, "Nobody", "Speed").is_err());
+    }
+
+    #[test]
+    fn set_changes_every_matching_entity() {
+        let (mut world, first, second) = world(true);
+
+        set(&mut world, "Mover", "Speed.max", "2.5").unwrap();
+
+        assert_eq!(speed(&world, first), (2.5, true));
+        assert_eq!(speed(&world, second), (2.5, true));
+    }
+
+    #[test]
+    fn set_changes_nothing_when_the_value_is_invalid() {
+        let (mut world, first, _) = world(true);
+
+        assert!(set(&mut world, "Mover", "Speed.max", "fast").is_err());
+
+        assert_eq!(speed(&world, first), (1.0, false));
+    }
+
+    #[test]
+    fn set_changes_nothing_when_an_entity_lacks_the_component() {
+        let (mut world, first, _) = world(false);
+
+        assert!(set(&mut world, "Mover", "Speed.max", "2.5").is_err());
+
+        assert_eq!(speed(&world, first), (1.0, false));
+    }
+
+    #[test]
+    fn insert_builds_the_component_for_every_entity() {
+        let (mut world, first, second) = world(false);
+
+        assert_eq!(
+            insert(&mut world, "Mover", &["(max:", "3.0)"])
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert_eq!(speed(&world, first).0, 3.0);
+        assert_eq!(speed(&world, second).0, 3.0);
+    }
+
+    #[test]
+    fn insert_uses_the_default_without_a_value() {
+        let (mut world, _, second) = world(false);
+
+        insert(&mut world, &second.to_string(), &[]).unwrap();
+
+        assert_eq!(speed(&world, second).0, 0.0);
+    }
+
+    #[test]
+    fn insert_changes_nothing_when_the_value_is_invalid() {
+        let (mut world, first, second) = world(false);
+
+        assert!(insert(&mut world, "Mover", &["(max: fast)"]).is_err());
+
+        assert_eq!(speed(&world, first), (1.0, false));
+        assert!(!world.entity(second).contains::<Speed>());
+    }
+
+    #[test]
+    fn remove_removes_the_component_from_every_entity() {
+        let (mut world, first, second) = world(true);
+
+        assert_eq!(remove(&mut world, "Speed").unwrap().len(), 2);
+
+        assert!(!world.entity(first).contains::<Speed>());
+        assert!(!world.entity(second).contains::<Speed>());
+        assert!(remove(&mut world, "Nobody").is_err());
+    }
+}
@@ -2,173 +2,111 @@
 //! This is based on multiple examples and may be very different for your game.
 //! - [Sprite flipping](https://github.com/bevyengine/bevy/blob/latest/examples/2d/sprite_flipping.rs)
 //! - [Sprite animation](https://github.com/bevyengine/bevy/blob/latest/examples/2d/sprite_animation.rs)
-//! - [Timers](https://github.com/bevyengine/bevy/blob/latest/examples/time/timers.rs)
+//!
+//! The frames and timings of each clip live in `assets/animations/ducky.anim.ron`
+//! and are played by a generic [`SpriteAnimator`]. This module only decides which
+//! clip to play, and listens for the clips' `"footstep"` events.
 
 use bevy::prelude::*;
 use rand::prelude::*;
-use std::time::Duration;
 
 use crate::{
     AppSystems, PausableSystems,
+    animation::{SpriteAnimationEvent, SpriteAnimator},
     audio::sound_effect,
     demo::{
         movement::MovementController,
         store::{DemoAssets, Store},
     },
+    rng::{GameRng, RngStream},
+    save::RegisterSaveable,
 };
 
 /// Component that tracks player's animation state.
-/// It is tightly bound to the texture atlas we use.
-#[derive(Component, Reflect)]
+#[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct PlayerAnimation {
-    timer: Timer,
-    frame: usize,
     state: PlayerAnimationState,
 }
 
-#[derive(Reflect, PartialEq)]
+#[derive(Reflect, PartialEq, Default)]
 pub enum PlayerAnimationState {
+    #[default]
     Idling,
     Walking,
 }
 
+impl PlayerAnimationState {
+    /// The name of the clip in the animation library for this state.
+    fn clip(&self) -> &'static str {
+        match self {
+            Self::Idling => "idle",
+            Self::Walking => "walk",
+        }
+    }
+}
+
 impl PlayerAnimation {
     pub(super) fn plugin(app: &mut App) {
         // Animate and play sound effects based on controls.
         app.register_type::<PlayerAnimation>();
+        app.save_component::<PlayerAnimation>();
         app.add_systems(
             Update,
-            (
-                Self::timer_driver.in_set(AppSystems::TickTimers),
-                (Self::driver, Self::atlas_driver, Self::step_sound_driver)
-                    .chain()
-                    .run_if(Store::is_ready)
-                    .in_set(AppSystems::Update),
-            )
+            Self::driver
+                .run_if(Store::is_ready)
+                .in_set(AppSystems::Update)
                 .in_set(PausableSystems),
         );
+        app.add_observer(Self::footstep_observer);
+    }
+
+    /// A [`SpriteAnimator`] playing the idle animation from the player's library.
+    pub fn animator(store: &Store) -> SpriteAnimator {
+        SpriteAnimator::new(
+            store.assets.ducky_animations.clone(),
+            PlayerAnimationState::Idling.clip(),
+        )
     }
 
     /// Update the sprite direction and animation state (idling/walking).
-    fn driver(mut player_query: Query<(&MovementController, &mut Sprite, &mut Self)>) {
-        for (controller, mut sprite, mut animation) in &mut player_query {
+    fn driver(
+        mut player_query: Query<(
+            &MovementController,
+            &mut Sprite,
+            &mut Self,
+            &mut SpriteAnimator,
+        )>,
+    ) {
+        for (controller, mut sprite, mut animation, mut animator) in &mut player_query {
             let dx = controller.intent.x;
             if dx != 0.0 {
                 sprite.flip_x = dx < 0.0;
             }
 
-            let animation_state = if controller.intent == Vec2::ZERO {
+            animation.state = if controller.intent == Vec2::ZERO {
                 PlayerAnimationState::Idling
             } else {
                 PlayerAnimationState::Walking
             };
-            animation.update_state(animation_state);
-        }
-    }
-
-    /// Update the animation timer.
-    fn timer_driver(time: Res<Time>, mut query: Query<&mut Self>) {
-        for mut animation in &mut query {
-            animation.update_timer(time.delta());
-        }
-    }
-
-    /// Update the texture atlas to reflect changes in the animation.
-    fn atlas_driver(mut query: Query<(&Self, &mut Sprite)>) {
-        for (animation, mut sprite) in &mut query {
-            let Some(atlas) = sprite.texture_atlas.as_mut() else {
-                continue;
-            };
-            if animation.changed() {
-                atlas.index = animation.get_atlas_index();
-            }
+            animator.play(animation.state.clip());
         }
     }
 
-    /// If the player is moving, play a step sound effect synchronized with the
-    /// animation.
-    fn step_sound_driver(
+    /// Play a step sound effect whenever the animation puts a foot down.
+    fn footstep_observer(
+        trigger: Trigger<SpriteAnimationEvent>,
         mut commands: Commands,
-        player_assets: Res<DemoAssets>,
-        mut step_query: Query<&Self>,
+        mut rng: ResMut<GameRng>,
+        player_assets: Option<Res<DemoAssets>>,
     ) {
-        for animation in &mut step_query {
-            if animation.state == PlayerAnimationState::Walking
-                && animation.changed()
-                && (animation.frame == 2 || animation.frame == 5)
-            {
-                let rng = &mut rand::thread_rng();
-                let random_step = player_assets.steps.choose(rng).unwrap().clone();
-                commands.spawn(sound_effect(random_step));
-            }
-        }
-    }
-}
-
-impl PlayerAnimation {
-    /// The number of idle frames.
-    const IDLE_FRAMES: usize = 2;
-    /// The duration of each idle frame.
-    const IDLE_INTERVAL: Duration = Duration::from_millis(500);
-    /// The number of walking frames.
-    const WALKING_FRAMES: usize = 6;
-    /// The duration of each walking frame.
-    const WALKING_INTERVAL: Duration = Duration::from_millis(50);
-
-    fn idling() -> Self {
-        Self {
-            timer: Timer::new(Self::IDLE_INTERVAL, TimerMode::Repeating),
-            frame: 0,
-            state: PlayerAnimationState::Idling,
-        }
-    }
-
-    fn walking() -> Self {
-        Self {
-            timer: Timer::new(Self::WALKING_INTERVAL, TimerMode::Repeating),
-            frame: 0,
-            state: PlayerAnimationState::Walking,
-        }
-    }
-
-    pub fn new() -> Self {
-        Self::idling()
-    }
-
-    /// Update animation timers.
-    pub fn update_timer(&mut self, delta: Duration) {
-        self.timer.tick(delta);
-        if !self.timer.finished() {
+        let Some(player_assets) = player_assets else {
             return;
-        }
-        self.frame = (self.frame + 1)
-            % match self.state {
-                PlayerAnimationState::Idling => Self::IDLE_FRAMES,
-                PlayerAnimationState::Walking => Self::WALKING_FRAMES,
-            };
-    }
-
-    /// Update animation state if it changes.
-    pub fn update_state(&mut self, state: PlayerAnimationState) {
-        if self.state != state {
-            match state {
-                PlayerAnimationState::Idling => *self = Self::idling(),
-                PlayerAnimationState::Walking => *self = Self::walking(),
-            }
-        }
-    }
-
-    /// Whether animation changed this tick.
-    pub fn changed(&self) -> bool {
-        self.timer.finished()
-    }
-
-    /// Return sprite index in the atlas.
-    pub fn get_atlas_index(&self) -> usize {
-        match self.state {
-            PlayerAnimationState::Idling => self.frame,
-            PlayerAnimationState::Walking => 6 + self.frame,
+        };
+        if trigger.name == "footstep" {
+            let rng = rng.stream(RngStream::Audio);
+            let random_step = player_assets.steps.choose(rng).unwrap().clone();
+            commands.spawn(sound_effect(random_step));
         }
     }
 }
@@ -0,0 +1,442 @@
+//! Lightweight kinematic collision for [`MovementController`] entities.
+//!
+//! Entities with a [`Collider`] take part in collision:
+//! - Entities with a [`MovementController`] are pushed out of entities without
+//!   one, so they slide along walls and obstacles.
+//! - Two entities with a [`MovementController`] push each other apart equally.
+//!
+//! [`CollisionStarted`] and [`CollisionEnded`] are triggered on both entities
+//! whenever two colliders start or stop touching.
+//!
+//! This works entirely on [`PhysicalTranslation`]s in [`FixedUpdate`], so it
+//! doesn't need a window or a GPU. Colliders are assumed to be unrotated and
+//! unscaled, and their [`PhysicalTranslation`] to be in world space.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::demo::movement::{
+    MovementController, MovementSystems, PhysicalTranslation, PreviousPhysicalTranslation, Velocity,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Collider>();
+    app.register_type::<CollisionStarted>();
+    app.register_type::<CollisionEnded>();
+    app.init_resource::<ActiveCollisions>();
+
+    app.add_systems(
+        FixedUpdate,
+        resolve_collisions.in_set(MovementSystems::Collide),
+    );
+}
+
+/// Colliders closer than this are still considered to be touching, so that
+/// resting against a wall doesn't end and restart the collision every tick.
+const CONTACT_MARGIN: f32 = 0.5;
+
+/// The shape of an entity for collision, centered on its [`PhysicalTranslation`].
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+#[require(PhysicalTranslation, PreviousPhysicalTranslation)]
+pub enum Collider {
+    /// An axis-aligned box.
+    Aabb {
+        half_size: Vec2,
+    },
+    Circle {
+        radius: f32,
+    },
+}
+
+/// How two colliders overlap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    /// The direction from the first collider towards the second.
+    pub normal: Vec2,
+    /// How far the colliders overlap along the normal.
+    /// Negative if they are separated by less than [`CONTACT_MARGIN`].
+    pub depth: f32,
+}
+
+impl Collider {
+    /// Half the size of the smallest box around the collider.
+    pub fn half_extents(&self) -> Vec2 {
+        match *self {
+            Self::Aabb { half_size } => half_size,
+            Self::Circle { radius } => Vec2::splat(radius),
+        }
+    }
+
+    /// The contact between this collider at `position` and `other` at `other_position`,
+    /// if they are touching.
+    pub fn contact(&self, position: Vec2, other: &Self, other_position: Vec2) -> Option<Contact> {
+        let offset = other_position - position;
+        let contact = match (*self, *other) {
+            (Self::Aabb { half_size: a }, Self::Aabb { half_size: b }) => {
+                let overlap = a + b - offset.abs();
+                if overlap.x < overlap.y {
+                    Contact {
+                        normal: Vec2::X * sign(offset.x),
+                        depth: overlap.x,
+                    }
+                } else {
+                    Contact {
+                        normal: Vec2::Y * sign(offset.y),
+                        depth: overlap.y,
+                    }
+                }
+            }
+            (Self::Circle { radius: a }, Self::Circle { radius: b }) => {
+                let distance = offset.length();
+                Contact {
+                    normal: offset.try_normalize().unwrap_or(Vec2::X),
+                    depth: a + b - distance,
+                }
+            }
+            (Self::Aabb { half_size }, Self::Circle { radius }) => {
+                box_circle_contact(half_size, offset, radius)
+            }
+            (Self::Circle { radius }, Self::Aabb { half_size }) => {
+                let contact = box_circle_contact(half_size, -offset, radius);
+                Contact {
+                    normal: -contact.normal,
+                    ..contact
+                }
+            }
+        };
+        (contact.depth >= -CONTACT_MARGIN).then_some(contact)
+    }
+}
+
+/// The contact between a box at the origin and a circle at `center`.
+fn box_circle_contact(half_size: Vec2, center: Vec2, radius: f32) -> Contact {
+    let closest = center.clamp(-half_size, half_size);
+    if closest != center {
+        // The center of the circle is outside of the box.
+        let offset = center - closest;
+        let distance = offset.length();
+        return Contact {
+            normal: offset / distance,
+            depth: radius - distance,
+        };
+    }
+
+    // The center of the circle is inside of the box, so push it out through the closest side.
+    let overlap = half_size - center.abs();
+    if overlap.x < overlap.y {
+        Contact {
+            normal: Vec2::X * sign(center.x),
+            depth: overlap.x + radius,
+        }
+    } else {
+        Contact {
+            normal: Vec2::Y * sign(center.y),
+            depth: overlap.y + radius,
+        }
+    }
+}
+
+/// Like [`f32::signum`], but treats zero as positive.
+fn sign(value: f32) -> f32 {
+    if value < 0.0 { -1.0 } else { 1.0 }
+}
+
+/// Triggered on both entities when their colliders start touching.
+#[derive(Event, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionStarted {
+    /// The other entity in the collision.
+    pub other: Entity,
+}
+
+/// Triggered on both entities when their colliders stop touching, unless the
+/// entity has been despawned.
+#[derive(Event, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionEnded {
+    /// The other entity in the collision.
+    pub other: Entity,
+}
+
+/// The pairs of entities that are touching, with the smaller entity first.
+#[derive(Resource, Default, Debug)]
+pub(super) struct ActiveCollisions(HashSet<(Entity, Entity)>);
+
+fn ordered_pair(a: Entity, b: Entity) -> (Entity, Entity) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+pub(super) fn resolve_collisions(
+    mut commands: Commands,
+    mut active_collisions: ResMut<ActiveCollisions>,
+    mut dynamic_query: Query<
+        (Entity, &Collider, &mut PhysicalTranslation, &mut Velocity),
+        With<MovementController>,
+    >,
+    static_query: Query<(Entity, &Collider, &PhysicalTranslation), Without<MovementController>>,
+) {
+    let mut collisions = HashSet::new();
+
+    // Push moving entities out of static ones.
+    for (entity, collider, mut translation, mut velocity) in &mut dynamic_query {
+        for (other, other_collider, other_translation) in &static_query {
+            let Some(contact) =
+                collider.contact(translation.0, other_collider, other_translation.0)
+            else {
+                continue;
+            };
+            collisions.insert(ordered_pair(entity, other));
+            if contact.depth > 0.0 {
+                translation.0 -= contact.normal * contact.depth;
+                // Slide along the obstacle instead of pushing into it.
+                let push = velocity.dot(contact.normal).max(0.0);
+                velocity.0 -= contact.normal * push;
+            }
+        }
+    }
+
+    // Push moving entities apart from each other.
+    let mut combinations = dynamic_query.iter_combinations_mut();
+    while let Some(
+        [
+            (a, a_collider, mut a_translation, _),
+            (b, b_collider, mut b_translation, _),
+        ],
+    ) = combinations.fetch_next()
+    {
+        let Some(contact) = a_collider.contact(a_translation.0, b_collider, b_translation.0) else {
+            continue;
+        };
+        collisions.insert(ordered_pair(a, b));
+        if contact.depth > 0.0 {
+            let push = contact.normal * contact.depth / 2.0;
+            a_translation.0 -= push;
+            b_translation.0 += push;
+        }
+    }
+
+    for &(a, b) in collisions.difference(&active_collisions.0) {
+        commands.trigger_targets(CollisionStarted { other: b }, a);
+        commands.trigger_targets(CollisionStarted { other: a }, b);
+    }
+    for &(a, b) in active_collisions.0.difference(&collisions) {
+        for (entity, other) in [(a, b), (b, a)] {
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.trigger(CollisionEnded { other });
+            }
+        }
+    }
+    active_collisions.0 = collisions;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn assert_contact(actual: Option<Contact>, normal: Vec2, depth: f32) {
+        let contact = actual.expect("the colliders should be touching");
+        assert!(
+            contact.normal.abs_diff_eq(normal, 1e-5) && (contact.depth - depth).abs() < 1e-5,
+            "expected a contact along {normal} with depth {depth}, not {contact:?}"
+        );
+    }
+
+    #[test]
+    fn boxes_touch_along_the_smallest_overlap() {
+        let a = Collider::Aabb {
+            half_size: Vec2::splat(10.0),
+        };
+        let b = Collider::Aabb {
+            half_size: Vec2::splat(5.0),
+        };
+        assert_contact(
+            a.contact(Vec2::ZERO, &b, Vec2::new(12.0, 0.0)),
+            Vec2::X,
+            3.0,
+        );
+        assert_contact(
+            a.contact(Vec2::ZERO, &b, Vec2::new(2.0, -13.0)),
+            -Vec2::Y,
+            2.0,
+        );
+        // Separated, but within the contact margin.
+        assert_contact(
+            a.contact(Vec2::ZERO, &b, Vec2::new(15.25, 0.0)),
+            Vec2::X,
+            -0.25,
+        );
+        assert_eq!(a.contact(Vec2::ZERO, &b, Vec2::new(20.0, 0.0)), None);
+    }
+
+    #[test]
+    fn circles_touch_along_the_line_between_them() {
+        let circle = Collider::Circle { radius: 5.0 };
+        assert_contact(
+            circle.contact(Vec2::ONE, &circle, Vec2::new(7.0, 9.0)),
+            Vec2::new(0.6, 0.8),
+            0.0,
+        );
+        assert_contact(
+            circle.contact(Vec2::ZERO, &circle, Vec2::new(0.0, 8.0)),
+            Vec2::Y,
+            2.0,
+        );
+        assert_eq!(
+            circle.contact(Vec2::ZERO, &circle, Vec2::new(0.0, 11.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn box_and_circle_contacts_point_from_the_first_to_the_second() {
+        let aabb = Collider::Aabb {
+            half_size: Vec2::splat(10.0),
+        };
+        let circle = Collider::Circle { radius: 5.0 };
+        assert_contact(
+            aabb.contact(Vec2::ZERO, &circle, Vec2::new(12.0, 0.0)),
+            Vec2::X,
+            3.0,
+        );
+        assert_contact(
+            circle.contact(Vec2::new(12.0, 0.0), &aabb, Vec2::ZERO),
+            -Vec2::X,
+            3.0,
+        );
+    }
+
+    #[test]
+    fn circle_outside_of_box_touches_the_closest_point() {
+        let contact = box_circle_contact(Vec2::splat(10.0), Vec2::new(13.0, 14.0), 6.0);
+        assert_contact(Some(contact), Vec2::new(0.6, 0.8), 1.0);
+    }
+
+    #[test]
+    fn circle_inside_of_box_is_pushed_out_through_the_closest_side() {
+        let contact = box_circle_contact(Vec2::splat(10.0), Vec2::new(8.0, -3.0), 2.0);
+        assert_contact(Some(contact), Vec2::X, 4.0);
+        let contact = box_circle_contact(Vec2::splat(10.0), Vec2::new(1.0, -9.0), 2.0);
+        assert_contact(Some(contact), -Vec2::Y, 3.0);
+    }
+
+    fn spawn_mover(
+        world: &mut World,
+        collider: Collider,
+        position: Vec2,
+        velocity: Vec2,
+    ) -> Entity {
+        world
+            .spawn((
+                MovementController::default(),
+                collider,
+                PhysicalTranslation(position),
+                Velocity(velocity),
+            ))
+            .id()
+    }
+
+    fn spawn_obstacle(world: &mut World, collider: Collider, position: Vec2) -> Entity {
+        world.spawn((collider, PhysicalTranslation(position))).id()
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<ActiveCollisions>();
+        world
+    }
+
+    #[test]
+    fn movers_slide_along_obstacles() {
+        let mut world = world();
+        let mover = spawn_mover(
+            &mut world,
+            Collider::Aabb {
+                half_size: Vec2::splat(5.0),
+            },
+            Vec2::new(12.0, 0.0),
+            Vec2::new(-100.0, 50.0),
+        );
+        spawn_obstacle(
+            &mut world,
+            Collider::Aabb {
+                half_size: Vec2::splat(10.0),
+            },
+            Vec2::ZERO,
+        );
+
+        world.run_system_once(resolve_collisions).unwrap();
+
+        let entity = world.entity(mover);
+        assert_eq!(
+            entity.get::<PhysicalTranslation>().unwrap().0,
+            Vec2::new(15.0, 0.0)
+        );
+        assert_eq!(entity.get::<Velocity>().unwrap().0, Vec2::new(0.0, 50.0));
+    }
+
+    #[test]
+    fn movers_push_each_other_apart_equally() {
+        let mut world = world();
+        let circle = Collider::Circle { radius: 5.0 };
+        let a = spawn_mover(&mut world, circle, Vec2::ZERO, Vec2::ZERO);
+        let b = spawn_mover(&mut world, circle, Vec2::new(8.0, 0.0), Vec2::ZERO);
+
+        world.run_system_once(resolve_collisions).unwrap();
+
+        let translation =
+            |entity: Entity| world.entity(entity).get::<PhysicalTranslation>().unwrap().0;
+        assert_eq!(translation(a), Vec2::new(-1.0, 0.0));
+        assert_eq!(translation(b), Vec2::new(9.0, 0.0));
+    }
+
+    /// The collision events that were triggered, as the target, whether the
+    /// collision started, and the other entity.
+    #[derive(Resource, Default)]
+    struct Triggered(Vec<(Entity, bool, Entity)>);
+
+    fn resolve_and_take_triggered(world: &mut World) -> Vec<(Entity, bool, Entity)> {
+        world.run_system_once(resolve_collisions).unwrap();
+        let mut triggered = std::mem::take(&mut world.resource_mut::<Triggered>().0);
+        triggered.sort();
+        triggered
+    }
+
+    #[test]
+    fn collisions_start_and_end_once_on_both_entities() {
+        let mut world = world();
+        world.init_resource::<Triggered>();
+        world.add_observer(
+            |trigger: Trigger<CollisionStarted>, mut triggered: ResMut<Triggered>| {
+                triggered
+                    .0
+                    .push((trigger.target(), true, trigger.event().other));
+            },
+        );
+        world.add_observer(
+            |trigger: Trigger<CollisionEnded>, mut triggered: ResMut<Triggered>| {
+                triggered
+                    .0
+                    .push((trigger.target(), false, trigger.event().other));
+            },
+        );
+        let circle = Collider::Circle { radius: 5.0 };
+        let mover = spawn_mover(&mut world, circle, Vec2::ZERO, Vec2::ZERO);
+        let obstacle = spawn_obstacle(&mut world, circle, Vec2::new(10.0, 0.0));
+
+        let mut expected = vec![(mover, true, obstacle), (obstacle, true, mover)];
+        expected.sort();
+        assert_eq!(resolve_and_take_triggered(&mut world), expected);
+        // Still touching.
+        assert_eq!(resolve_and_take_triggered(&mut world), vec![]);
+
+        world
+            .entity_mut(mover)
+            .insert(PhysicalTranslation(Vec2::new(-5.0, 0.0)));
+        let mut expected = vec![(mover, false, obstacle), (obstacle, false, mover)];
+        expected.sort();
+        assert_eq!(resolve_and_take_triggered(&mut world), expected);
+        assert_eq!(resolve_and_take_triggered(&mut world), vec![]);
+    }
+}
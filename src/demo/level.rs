@@ -1,30 +1,158 @@
-//! Spawn the main level.
+//! Spawn levels.
+//!
+//! Levels are described by `*.level.ron` files in `assets/levels`, which list
+//! the entities to place, along with level-wide settings such as the music,
+//! the tile map and the world bounds. Designers can add or change levels without recompiling.
 
-use bevy::prelude::*;
+use bevy::{asset::LoadContext, prelude::*};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     audio::music,
-    demo::{player::Player, store::Store},
-    screens::Screen,
+    demo::{
+        collision::Collider,
+        player::Player,
+        progression::LevelState,
+        store::Store,
+        tilemap::{MapDescription, Tilemap},
+    },
+    ron_asset::{InitRonAsset, RonAsset},
+    save::SaveId,
 };
 
-#[derive(Component)]
-pub struct Level;
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Level>();
+    app.register_type::<LevelBounds>();
+    app.register_type::<LevelDescription>();
+    app.init_ron_asset::<LevelDescription>();
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Level {
+    /// The human-readable name from the [`LevelDescription`].
+    pub name: String,
+}
+
+/// The area of the world that a level takes up.
+#[derive(Component, Reflect, Clone, Copy, Debug, Deref)]
+#[reflect(Component)]
+pub struct LevelBounds(pub Rect);
+
+/// The contents of a `*.level.ron` file.
+#[derive(Asset, Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct LevelDescription {
+    /// A human-readable name.
+    pub name: String,
+    /// Asset path of the music that loops during the level.
+    pub music: String,
+    /// Asset path of the tile map (`*.map.ron` or `*.tmx`), if any.
+    #[serde(default)]
+    pub map: Option<String>,
+    /// How many world units one unit of the tile map takes up. Tiled maps
+    /// measure their tiles in pixels, so they usually need a larger scale.
+    #[serde(default = "default_tile_scale")]
+    pub tile_scale: f32,
+    /// The area of the world that the level takes up.
+    pub bounds: Rect,
+    /// The entities placed in the level.
+    pub entities: Vec<LevelEntity>,
+    /// The handle of [`Self::music`], loaded along with the level.
+    #[serde(skip)]
+    #[dependency]
+    pub music_handle: Handle<AudioSource>,
+    /// The handle of [`Self::map`], loaded along with the level.
+    #[serde(skip)]
+    #[dependency]
+    pub map_handle: Option<Handle<MapDescription>>,
+}
+
+fn default_tile_scale() -> f32 {
+    1.0
+}
+
+impl RonAsset for LevelDescription {
+    const EXTENSIONS: &'static [&'static str] = &["level.ron"];
+
+    fn load_dependencies(&mut self, load_context: &mut LoadContext) {
+        self.music_handle = load_context.load(&self.music);
+        self.map_handle = self.map.as_ref().map(|path| load_context.load(path));
+    }
+}
+
+/// An entity placed in a level.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct LevelEntity {
+    pub prefab: Prefab,
+    pub position: Vec2,
+}
+
+/// Everything that can be placed in a level, along with its parameters.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
+pub enum Prefab {
+    Player {
+        max_speed: f32,
+    },
+    /// An obstacle that blocks movement.
+    Crate {
+        half_size: Vec2,
+    },
+}
 
 impl Level {
-    /// A system that spawns the main level.
-    pub fn level(store: &mut Store) -> impl Bundle {
-        let music = music(store.assets.music.clone());
-        (
-            Level,
-            Name::new("Level"),
+    /// Spawn the level at `index` in the level list and all of its entities.
+    pub fn spawn(commands: &mut Commands, store: &Store, index: usize) {
+        let Some(description) = store
+            .level_lists
+            .get(&store.assets.levels)
+            .and_then(|list| list.handles.get(index))
+            .and_then(|handle| store.levels.get(handle))
+        else {
+            error!("Level {index} was requested, but it does not exist or is not loaded.");
+            return;
+        };
+
+        let mut level = commands.spawn((
+            Level {
+                name: description.name.clone(),
+            },
+            Name::new(format!("Level: {}", description.name)),
+            LevelBounds(description.bounds),
             Transform::default(),
             Visibility::default(),
-            StateScoped(Screen::Gameplay),
-            children![
-                Player::player(store, 400.0),
-                (Name::new("Gameplay Music"), music),
-            ],
-        )
+            StateScoped(LevelState::Playing),
+        ));
+        level.with_child((
+            Name::new("Gameplay Music"),
+            music(description.music_handle.clone()),
+        ));
+        if let Some(map) = description
+            .map_handle
+            .as_ref()
+            .and_then(|handle| store.maps.get(handle))
+        {
+            Tilemap::spawn(&mut level, map, description.tile_scale);
+        }
+        for (index, entity) in description.entities.iter().enumerate() {
+            // The position in the level file identifies the entity in saves.
+            let save_id = SaveId(index as u32);
+            match entity.prefab {
+                Prefab::Player { max_speed } => {
+                    level.with_child((
+                        Player::player(store, max_speed, entity.position, description.bounds),
+                        save_id,
+                    ));
+                }
+                Prefab::Crate { half_size } => {
+                    level.with_child((
+                        Name::new("Crate"),
+                        Sprite::from_color(Color::srgb(0.55, 0.38, 0.2), half_size * 2.0),
+                        Transform::from_translation(entity.position.extend(0.0)),
+                        Collider::Aabb { half_size },
+                        save_id,
+                    ));
+                }
+            }
+        }
     }
 }
@@ -8,16 +8,23 @@ use bevy::prelude::*;
 use crate::demo::{animation::PlayerAnimation, player::Player, store::Store};
 
 mod animation;
+pub mod collision;
 pub mod level;
-mod movement;
+pub mod movement;
 pub mod player;
+pub mod progression;
 pub mod store;
+pub mod tilemap;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         PlayerAnimation::plugin,
+        collision::plugin,
+        level::plugin,
         movement::plugin,
         Player::plugin,
+        progression::plugin,
         Store::plugin,
+        tilemap::plugin,
     ));
 }
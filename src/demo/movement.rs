@@ -3,31 +3,77 @@
 //! the movement of characters.
 //!
 //! In our case, the character controller has the following logic:
-//! - Set [`MovementController`] intent based on directional keyboard input.
+//! - Set [`MovementController`] intent based on directional keyboard or gamepad
+//!   input.
 //!   This is done in the `player` module, as it is specific to the player
 //!   character.
-//! - Apply movement based on [`MovementController`] intent and maximum speed.
-//! - Wrap the character within the window.
+//! - Accelerate the velocity towards the [`MovementController`] intent, and
+//!   apply it. Entities can also [`Dash`], and sprint while they have
+//!   [`Stamina`] left.
+//! - Keep the character within an arena, see [`ScreenWrap`].
 //!
-//! Note that the implementation used here is limited for demonstration
-//! purposes. If you want to move the player in a smoother way,
-//! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/main/examples/movement/physics_in_fixed_timestep.rs).
+//! Movement is simulated in [`FixedUpdate`] on a [`PhysicalTranslation`], which
+//! makes it independent of the frame rate. The rendered [`Transform`] is then
+//! interpolated between the last two fixed ticks, as shown in the
+//! [fixed timestep example](https://github.com/bevyengine/bevy/blob/main/examples/movement/physics_in_fixed_timestep.rs).
+//! The tick rate can be changed through [`Time<Fixed>`].
 
 use bevy::{prelude::*, window::PrimaryWindow};
 
-use crate::{AppSystems, PausableSystems};
+use crate::{PausableSystems, save::RegisterSaveable};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<MovementController>();
+    app.register_type::<Velocity>();
+    app.register_type::<Dash>();
+    app.register_type::<Stamina>();
     app.register_type::<ScreenWrap>();
+    app.register_type::<PhysicalTranslation>();
+    app.register_type::<PreviousPhysicalTranslation>();
+    app.save_component::<MovementController>();
+    app.save_component::<Velocity>();
+    app.save_component::<Dash>();
+    app.save_component::<Stamina>();
+    app.save_component::<ScreenWrap>();
+    app.save_component::<PhysicalTranslation>();
+    app.save_component::<PreviousPhysicalTranslation>();
 
-    app.add_systems(
-        Update,
-        (apply_movement, apply_screen_wrap)
+    app.add_observer(init_physical_translation);
+    app.configure_sets(
+        FixedUpdate,
+        (
+            MovementSystems::Move,
+            MovementSystems::Collide,
+            MovementSystems::Wrap,
+        )
             .chain()
-            .in_set(AppSystems::Update)
             .in_set(PausableSystems),
     );
+    app.add_systems(
+        FixedUpdate,
+        (
+            store_previous_physical_translation.before(MovementSystems::Move),
+            (update_dashes, apply_movement)
+                .chain()
+                .in_set(MovementSystems::Move),
+            apply_screen_wrap.in_set(MovementSystems::Wrap),
+        ),
+    );
+    app.add_systems(
+        RunFixedMainLoop,
+        interpolate_rendered_transform.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+    );
+}
+
+/// The steps of the movement simulation in [`FixedUpdate`], in order.
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MovementSystems {
+    /// Move entities according to their [`MovementController`].
+    Move,
+    /// Undo movement into obstacles.
+    Collide,
+    /// Keep entities on the screen.
+    Wrap,
 }
 
 /// These are the movement parameters for our character controller.
@@ -35,48 +81,444 @@ pub(super) fn plugin(app: &mut App) {
 /// other players as well.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
+#[require(Velocity, PhysicalTranslation, PreviousPhysicalTranslation)]
 pub struct MovementController {
     /// The direction the character wants to move in.
     pub intent: Vec2,
+    /// Whether the character wants to sprint. This only has an effect if it
+    /// has [`Stamina`] left.
+    pub sprint: bool,
 
     /// Maximum speed in world units per second.
     /// 1 world unit = 1 pixel when using the default 2D camera and no physics engine.
     pub max_speed: f32,
+    /// How quickly the character speeds up, in world units per second squared.
+    pub acceleration: f32,
+    /// How quickly the character slows down when there is no intent.
+    pub deceleration: f32,
+    /// How quickly the character changes direction when the intent points
+    /// away from the current velocity. Higher values feel more responsive.
+    pub turn_acceleration: f32,
+    /// The maximum speed is multiplied by this while sprinting.
+    pub sprint_multiplier: f32,
 }
 
 impl Default for MovementController {
     fn default() -> Self {
         Self {
             intent: Vec2::ZERO,
+            sprint: false,
             // 400 pixels per second is a nice default, but we can still vary this per character.
             max_speed: 400.0,
+            acceleration: 3000.0,
+            deceleration: 2500.0,
+            turn_acceleration: 6000.0,
+            sprint_multiplier: 1.6,
+        }
+    }
+}
+
+/// How fast an entity is moving, in world units per second.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct Velocity(pub Vec2);
+
+/// A quick burst of speed that overrides the [`Velocity`] for a short time.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct Dash {
+    /// Set this to dash as soon as possible. It is reset on the next fixed tick,
+    /// even if the dash is still cooling down.
+    pub requested: bool,
+    /// The speed during the dash, in world units per second.
+    pub speed: f32,
+    direction: Vec2,
+    duration: Timer,
+    cooldown: Timer,
+}
+
+impl Dash {
+    pub fn new(speed: f32, duration_secs: f32, cooldown_secs: f32) -> Self {
+        let mut duration = Timer::from_seconds(duration_secs, TimerMode::Once);
+        let mut cooldown = Timer::from_seconds(cooldown_secs, TimerMode::Once);
+        // Start out ready to dash.
+        duration.tick(duration.duration());
+        cooldown.tick(cooldown.duration());
+        Self {
+            requested: false,
+            speed,
+            direction: Vec2::ZERO,
+            duration,
+            cooldown,
         }
     }
+
+    pub fn is_dashing(&self) -> bool {
+        !self.duration.finished()
+    }
+}
+
+/// Limits how long an entity can sprint.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    /// How much stamina sprinting uses per second.
+    pub drain: f32,
+    /// How much stamina is recovered per second while not sprinting.
+    pub regeneration: f32,
 }
 
-fn apply_movement(
+impl Stamina {
+    pub fn new(max: f32, drain: f32, regeneration: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            drain,
+            regeneration,
+        }
+    }
+}
+
+/// The position of an entity in the fixed timestep simulation.
+/// Gameplay logic should read and write this instead of the [`Transform`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct PhysicalTranslation(pub Vec2);
+
+/// The [`PhysicalTranslation`] at the previous fixed tick, used for interpolation.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct PreviousPhysicalTranslation(pub Vec2);
+
+/// Start the simulation wherever the entity was spawned.
+fn init_physical_translation(
+    trigger: Trigger<OnAdd, PhysicalTranslation>,
+    mut translation_query: Query<(
+        &Transform,
+        &mut PhysicalTranslation,
+        &mut PreviousPhysicalTranslation,
+    )>,
+) {
+    let Ok((transform, mut current, mut previous)) = translation_query.get_mut(trigger.target())
+    else {
+        return;
+    };
+    current.0 = transform.translation.xy();
+    previous.0 = current.0;
+}
+
+/// This runs even while the game is paused, so that the rendered position
+/// comes to a rest instead of jittering between the last two ticks.
+fn store_previous_physical_translation(
+    mut translation_query: Query<(&PhysicalTranslation, &mut PreviousPhysicalTranslation)>,
+) {
+    for (current, mut previous) in &mut translation_query {
+        previous.0 = current.0;
+    }
+}
+
+/// Start requested dashes, and tick the dash timers.
+fn update_dashes(
     time: Res<Time>,
-    mut movement_query: Query<(&MovementController, &mut Transform)>,
+    mut dash_query: Query<(&mut Dash, &MovementController, &Velocity)>,
 ) {
-    for (controller, mut transform) in &mut movement_query {
-        let velocity = controller.max_speed * controller.intent;
-        transform.translation += velocity.extend(0.0) * time.delta_secs();
+    for (mut dash, controller, velocity) in &mut dash_query {
+        dash.duration.tick(time.delta());
+        dash.cooldown.tick(time.delta());
+        if !std::mem::take(&mut dash.requested) || !dash.cooldown.finished() {
+            continue;
+        }
+
+        // Dash where the character wants to go, or else keep going the same way.
+        let Some(direction) = controller
+            .intent
+            .try_normalize()
+            .or_else(|| velocity.try_normalize())
+        else {
+            continue;
+        };
+        dash.direction = direction;
+        dash.duration.reset();
+        dash.cooldown.reset();
     }
 }
 
-#[derive(Component, Reflect)]
+pub(super) fn apply_movement(
+    time: Res<Time>,
+    mut movement_query: Query<(
+        &MovementController,
+        &mut Velocity,
+        &mut PhysicalTranslation,
+        Option<&Dash>,
+        Option<&mut Stamina>,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (controller, mut velocity, mut translation, dash, stamina) in &mut movement_query {
+        let mut max_speed = controller.max_speed;
+        if let Some(mut stamina) = stamina {
+            let sprinting =
+                controller.sprint && controller.intent != Vec2::ZERO && stamina.current > 0.0;
+            if sprinting {
+                max_speed *= controller.sprint_multiplier;
+                stamina.current = (stamina.current - stamina.drain * dt).max(0.0);
+            } else if !controller.sprint {
+                // Only recover once the character lets go of sprint, so an exhausted
+                // character can't stutter-sprint.
+                stamina.current = (stamina.current + stamina.regeneration * dt).min(stamina.max);
+            }
+        }
+
+        if let Some(dash) = dash.filter(|dash| dash.is_dashing()) {
+            velocity.0 = dash.direction * dash.speed;
+        } else {
+            let target = controller.intent * max_speed;
+            let rate = if target == Vec2::ZERO {
+                controller.deceleration
+            } else if target.dot(velocity.0) < 0.0 {
+                controller.turn_acceleration
+            } else {
+                controller.acceleration
+            };
+            velocity.0 = velocity.0.move_towards(target, rate * dt);
+        }
+
+        translation.0 += velocity.0 * dt;
+    }
+}
+
+/// Keeps an entity within an arena.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
 #[reflect(Component)]
-pub struct ScreenWrap;
+#[require(PhysicalTranslation, PreviousPhysicalTranslation)]
+pub struct ScreenWrap {
+    /// What happens when the entity leaves the arena.
+    pub mode: WrapMode,
+    /// How far the entity can go beyond the edges of the arena before `mode`
+    /// applies, in world units. Use a negative margin to keep it further inside,
+    /// down to the center of the arena.
+    pub margin: f32,
+    pub arena: Arena,
+}
+
+impl Default for ScreenWrap {
+    fn default() -> Self {
+        Self {
+            mode: WrapMode::Wrap,
+            // Wrap only once the entity is off screen.
+            margin: 128.0,
+            arena: Arena::Window,
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Teleport to the opposite edge.
+    #[default]
+    Wrap,
+    /// Stop at the edge.
+    Clamp,
+    /// Reflect off of the edge, reversing the [`Velocity`].
+    Bounce,
+    /// Despawn the entity.
+    Despawn,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Arena {
+    /// The area of the world that the camera sees.
+    #[default]
+    Window,
+    /// A fixed area in world space.
+    Rect(Rect),
+}
 
 fn apply_screen_wrap(
-    window: Single<&Window, With<PrimaryWindow>>,
-    mut wrap_query: Query<&mut Transform, With<ScreenWrap>>,
+    mut commands: Commands,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    camera_query: Query<(&GlobalTransform, &Projection), With<Camera2d>>,
+    mut wrap_query: Query<(
+        Entity,
+        &ScreenWrap,
+        &mut PhysicalTranslation,
+        &mut PreviousPhysicalTranslation,
+        Option<&mut Velocity>,
+    )>,
 ) {
-    let size = window.size() + 256.0;
-    let half_size = size / 2.0;
-    for mut transform in &mut wrap_query {
-        let position = transform.translation.xy();
-        let wrapped = (position + half_size).rem_euclid(size) - half_size;
-        transform.translation = wrapped.extend(transform.translation.z);
+    // The visible area, or the window centered on the origin if there is no camera.
+    let window_arena = match camera_query.single() {
+        Ok((transform, Projection::Orthographic(projection))) => {
+            let center = transform.translation().xy();
+            Some(Rect::from_center_half_size(
+                center,
+                projection.area.half_size(),
+            ))
+        }
+        _ => window.map(|window| Rect::from_center_size(Vec2::ZERO, window.size())),
+    };
+
+    for (entity, wrap, mut translation, mut previous, velocity) in &mut wrap_query {
+        let arena = match wrap.arena {
+            Arena::Window => {
+                let Some(window_arena) = window_arena else {
+                    continue;
+                };
+                window_arena
+            }
+            Arena::Rect(rect) => rect,
+        };
+        // A negative margin can shrink the arena down to its center, but no further.
+        let arena = Rect::from_center_half_size(
+            arena.center(),
+            (arena.half_size() + wrap.margin).max(Vec2::ZERO),
+        );
+        if arena.contains(translation.0) {
+            continue;
+        }
+
+        match wrap.mode {
+            WrapMode::Wrap => {
+                let size = arena.size();
+                let wrapped = Vec2::select(
+                    size.cmpgt(Vec2::ZERO),
+                    (translation.0 - arena.min).rem_euclid(size) + arena.min,
+                    arena.min,
+                );
+                // Teleport the previous translation along, so we don't interpolate across the screen.
+                previous.0 += wrapped - translation.0;
+                translation.0 = wrapped;
+            }
+            WrapMode::Clamp => {
+                let clamped = translation.0.clamp(arena.min, arena.max);
+                if let Some(mut velocity) = velocity {
+                    // Stop pushing against the edge.
+                    velocity.0 = Vec2::select(clamped.cmpeq(translation.0), velocity.0, Vec2::ZERO);
+                }
+                translation.0 = clamped;
+            }
+            WrapMode::Bounce => {
+                // Mirror the position back into the arena.
+                let reflected = translation.0.clamp(
+                    2.0 * arena.min - translation.0,
+                    2.0 * arena.max - translation.0,
+                );
+                let outside = translation.0.cmplt(arena.min) | translation.0.cmpgt(arena.max);
+                translation.0 = reflected.clamp(arena.min, arena.max);
+                if let Some(mut velocity) = velocity {
+                    velocity.0 = Vec2::select(outside, -velocity.0, velocity.0);
+                }
+            }
+            WrapMode::Despawn => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+/// Render entities between their previous and current simulated positions,
+/// depending on how far we are into the next fixed tick.
+fn interpolate_rendered_transform(
+    fixed_time: Res<Time<Fixed>>,
+    mut transform_query: Query<(
+        &mut Transform,
+        &PhysicalTranslation,
+        &PreviousPhysicalTranslation,
+    )>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, current, previous) in &mut transform_query {
+        let rendered = previous.0.lerp(current.0, alpha);
+        transform.translation = rendered.extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// Apply a [`ScreenWrap`] around a 100 by 100 area without a window, and
+    /// return the new translation, previous translation and velocity.
+    fn wrap(mode: WrapMode, margin: f32, translation: Vec2, velocity: Vec2) -> (Vec2, Vec2, Vec2) {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                ScreenWrap {
+                    mode,
+                    margin,
+                    arena: Arena::Rect(Rect::from_center_size(Vec2::ZERO, Vec2::splat(100.0))),
+                },
+                PhysicalTranslation(translation),
+                PreviousPhysicalTranslation(translation),
+                Velocity(velocity),
+            ))
+            .id();
+        world.run_system_once(apply_screen_wrap).unwrap();
+        let entity = world.entity(entity);
+        (
+            entity.get::<PhysicalTranslation>().unwrap().0,
+            entity.get::<PreviousPhysicalTranslation>().unwrap().0,
+            entity.get::<Velocity>().unwrap().0,
+        )
+    }
+
+    #[test]
+    fn wrap_teleports_to_the_opposite_edge() {
+        let (translation, previous, _) = wrap(WrapMode::Wrap, 10.0, Vec2::new(65.0, 0.0), Vec2::X);
+        assert_eq!(translation, Vec2::new(-55.0, 0.0));
+        assert_eq!(previous, translation);
+    }
+
+    #[test]
+    fn clamp_stops_at_the_edge() {
+        let (translation, _, velocity) =
+            wrap(WrapMode::Clamp, 0.0, Vec2::new(60.0, 20.0), Vec2::ONE);
+        assert_eq!(translation, Vec2::new(50.0, 20.0));
+        assert_eq!(velocity, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn bounce_reflects_each_axis_separately() {
+        let (translation, _, velocity) = wrap(
+            WrapMode::Bounce,
+            0.0,
+            Vec2::new(60.0, 20.0),
+            Vec2::new(10.0, 5.0),
+        );
+        assert_eq!(translation, Vec2::new(40.0, 20.0));
+        assert_eq!(velocity, Vec2::new(-10.0, 5.0));
+
+        let (translation, _, velocity) = wrap(
+            WrapMode::Bounce,
+            0.0,
+            Vec2::new(0.0, -70.0),
+            Vec2::new(3.0, -4.0),
+        );
+        assert_eq!(translation, Vec2::new(0.0, -30.0));
+        assert_eq!(velocity, Vec2::new(3.0, 4.0));
+
+        let (translation, _, velocity) = wrap(
+            WrapMode::Bounce,
+            0.0,
+            Vec2::new(-55.0, 52.0),
+            Vec2::new(-1.0, 2.0),
+        );
+        assert_eq!(translation, Vec2::new(-45.0, 48.0));
+        assert_eq!(velocity, Vec2::new(1.0, -2.0));
+    }
+
+    #[test]
+    fn large_negative_margins_shrink_the_arena_to_its_center() {
+        let (translation, _, velocity) =
+            wrap(WrapMode::Clamp, -80.0, Vec2::new(30.0, -40.0), Vec2::ONE);
+        assert_eq!(translation, Vec2::ZERO);
+        assert_eq!(velocity, Vec2::ZERO);
+
+        let (translation, previous, _) =
+            wrap(WrapMode::Wrap, -80.0, Vec2::new(30.0, -40.0), Vec2::ONE);
+        assert_eq!(translation, Vec2::ZERO);
+        assert_eq!(previous, Vec2::ZERO);
     }
 }
@@ -4,86 +4,106 @@ use bevy::prelude::*;
 
 use crate::{
     AppSystems, PausableSystems,
+    camera::{CameraTarget, ScreenShake},
     demo::{
         animation::PlayerAnimation,
-        movement::{MovementController, ScreenWrap},
+        collision::{Collider, CollisionStarted},
+        movement::{Arena, Dash, MovementController, ScreenWrap, Stamina, WrapMode},
         store::Store,
     },
+    input::{Action, MoveAxis},
+    replay::is_replaying,
 };
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
+/// The duck's body is smaller than its sprite.
+const COLLIDER_RADIUS: f32 = 52.0;
+
 impl Player {
     pub(super) fn plugin(app: &mut App) {
         app.register_type::<Player>();
 
-        // Record directional input as movement controls.
+        // Record directional input as movement controls, unless a replay
+        // provides them instead.
         app.add_systems(
             Update,
             Self::directional_input_driver
                 .in_set(AppSystems::RecordInput)
-                .in_set(PausableSystems),
+                .in_set(PausableSystems)
+                .run_if(not(is_replaying)),
         );
+        app.add_observer(Self::bump_observer);
     }
 
-    /// The player character.
-    pub fn player(store: &mut Store, max_speed: f32) -> impl Bundle {
+    /// The player character, kept within the `bounds` of the level.
+    pub fn player(store: &Store, max_speed: f32, position: Vec2, bounds: Rect) -> impl Bundle {
         // A texture atlas is a way to split a single image into a grid of related images.
         // You can learn more in this example: https://github.com/bevyengine/bevy/blob/latest/examples/2d/texture_atlas.rs
-        let layout =
-            TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 2, Some(UVec2::splat(1)), None);
-        let texture_atlas_layout = store.texture_atlas_layouts.add(layout);
-        let player_animation = PlayerAnimation::new();
+        // The grid layout is defined by the animation library, along with the clips.
+        let texture_atlas_layout = store
+            .animation_libraries
+            .get(&store.assets.ducky_animations)
+            .map(|library| library.atlas_layout.clone())
+            .unwrap_or_default();
 
         (
             Name::new("Player"),
             Player,
+            CameraTarget,
             Sprite {
                 image: store.assets.ducky.clone(),
-                texture_atlas: Some(TextureAtlas {
-                    layout: texture_atlas_layout,
-                    index: player_animation.get_atlas_index(),
-                }),
+                texture_atlas: Some(texture_atlas_layout.into()),
                 ..default()
             },
-            Transform::from_scale(Vec2::splat(8.0).extend(1.0)),
+            Transform::from_translation(position.extend(0.0))
+                .with_scale(Vec2::splat(8.0).extend(1.0)),
             MovementController {
                 max_speed,
                 ..default()
             },
-            ScreenWrap,
-            player_animation,
+            Dash::new(1400.0, 0.15, 0.8),
+            Stamina::new(100.0, 35.0, 20.0),
+            Collider::Circle {
+                radius: COLLIDER_RADIUS,
+            },
+            ScreenWrap {
+                mode: WrapMode::Clamp,
+                margin: -COLLIDER_RADIUS,
+                arena: Arena::Rect(bounds),
+            },
+            PlayerAnimation::default(),
+            PlayerAnimation::animator(store),
         )
     }
 
-    fn directional_input_driver(
-        input: Res<ButtonInput<KeyCode>>,
-        mut controller_query: Query<&mut MovementController, With<Player>>,
+    /// Shake the screen a little when the player bumps into something.
+    fn bump_observer(
+        trigger: Trigger<CollisionStarted>,
+        player_query: Query<(), With<Player>>,
+        mut screen_shake: EventWriter<ScreenShake>,
     ) {
-        // Collect directional input.
-        let mut intent = Vec2::ZERO;
-        if input.pressed(KeyCode::KeyW) || input.pressed(KeyCode::ArrowUp) {
-            intent.y += 1.0;
-        }
-        if input.pressed(KeyCode::KeyS) || input.pressed(KeyCode::ArrowDown) {
-            intent.y -= 1.0;
-        }
-        if input.pressed(KeyCode::KeyA) || input.pressed(KeyCode::ArrowLeft) {
-            intent.x -= 1.0;
+        if player_query.contains(trigger.target()) {
+            screen_shake.write(ScreenShake { trauma: 0.3 });
         }
-        if input.pressed(KeyCode::KeyD) || input.pressed(KeyCode::ArrowRight) {
-            intent.x += 1.0;
-        }
-
-        // Normalize intent so that diagonal movement is the same speed as horizontal / vertical.
-        // This should be omitted if the input comes from an analog stick instead.
-        let intent = intent.normalize_or_zero();
+    }
 
-        // Apply movement intent to controllers.
-        for mut controller in &mut controller_query {
-            controller.intent = intent;
+    fn directional_input_driver(
+        move_axis: Res<MoveAxis>,
+        actions: Res<ButtonInput<Action>>,
+        mut controller_query: Query<(&mut MovementController, Option<&mut Dash>), With<Player>>,
+    ) {
+        for (mut controller, dash) in &mut controller_query {
+            // Apply movement intent to controllers. This is already analog and
+            // clamped to a length of 1, no matter which device it came from.
+            controller.intent = **move_axis;
+            controller.sprint = actions.pressed(Action::Sprint);
+            // Keep the request until the next fixed tick picks it up.
+            if let Some(mut dash) = dash {
+                dash.requested |= actions.just_pressed(Action::Dash);
+            }
         }
     }
 }
@@ -0,0 +1,292 @@
+//! Level progression: which level is being played, and switching between levels.
+//!
+//! Trigger a [`LoadLevel`] event to move to another level. The screen fades to
+//! black, the entities of the old level are despawned by leaving
+//! [`LevelState::Playing`], and the new level is spawned while the screen fades
+//! back in. All levels in the [`LevelList`] are loaded up front, so this never
+//! goes through the loading screen.
+
+use std::time::Duration;
+
+use bevy::{asset::LoadContext, prelude::*};
+use bevy_console::{AddConsoleCommand, ConsoleCommand, reply};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        level::{Level, LevelDescription},
+        store::{DemoAssets, Store},
+    },
+    ron_asset::{InitRonAsset, RonAsset},
+    save::RegisterSaveable,
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelList>();
+    app.init_ron_asset::<LevelList>();
+    app.add_sub_state::<LevelState>();
+
+    app.register_type::<CurrentLevel>();
+    app.register_type::<PlayTime>();
+    app.init_resource::<CurrentLevel>();
+    app.init_resource::<PlayTime>();
+    app.save_resource::<CurrentLevel>();
+    app.save_resource::<PlayTime>();
+    app.add_systems(OnExit(Screen::Gameplay), reset_progress);
+    app.add_systems(
+        Update,
+        tick_play_time
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+
+    app.add_observer(load_level);
+    app.add_systems(OnEnter(LevelState::Playing), spawn_current_level);
+    app.add_systems(OnEnter(LevelState::Switching), finish_switching);
+
+    app.register_type::<LevelTransition>();
+    app.add_systems(
+        Update,
+        (
+            tick_level_transition.in_set(AppSystems::TickTimers),
+            apply_level_transition.in_set(AppSystems::Update),
+        )
+            .run_if(in_state(Screen::Gameplay)),
+    );
+
+    app.add_console_command::<LevelCommand, _>(LevelCommand::driver);
+}
+
+/// The contents of a `*.levels.ron` file: the order in which levels are played.
+#[derive(Asset, Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct LevelList {
+    /// Level ids. The level with id `foo` is loaded from `levels/foo.level.ron`.
+    pub levels: Vec<String>,
+    /// The handles of [`Self::levels`], in the same order.
+    #[serde(skip)]
+    #[dependency]
+    pub handles: Vec<Handle<LevelDescription>>,
+}
+
+impl RonAsset for LevelList {
+    const EXTENSIONS: &'static [&'static str] = &["levels.ron"];
+
+    fn load_dependencies(&mut self, load_context: &mut LoadContext) {
+        self.handles = self
+            .levels
+            .iter()
+            .map(|id| load_context.load(format!("levels/{id}.level.ron")))
+            .collect();
+    }
+}
+
+impl LevelList {
+    /// The position of a level in the list.
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.levels.iter().position(|level| level == id)
+    }
+}
+
+/// Whether a level is being played. Entities that belong to a single level
+/// should be scoped to [`LevelState::Playing`], so they are despawned when
+/// switching levels.
+#[derive(SubStates, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[source(Screen = Screen::Gameplay)]
+#[states(scoped_entities)]
+pub enum LevelState {
+    #[default]
+    Playing,
+    /// The old level has been torn down and the new one is about to be spawned.
+    Switching,
+}
+
+/// The index of the level being played in the level list.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct CurrentLevel(pub usize);
+
+/// How long the current game has been played, not counting pauses.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct PlayTime(pub Duration);
+
+/// Triggered after a level and all of its entities have been spawned.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LevelSpawned;
+
+/// A request to switch levels. If this is triggered outside of gameplay, the
+/// level will be played the next time gameplay starts.
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub enum LoadLevel {
+    /// Continue with the next level in the list, wrapping around after the last.
+    Next,
+    /// Start the current level over.
+    Restart,
+    /// Switch to the level with this id.
+    Id(String),
+}
+
+fn load_level(
+    trigger: Trigger<LoadLevel>,
+    mut commands: Commands,
+    assets: Option<Res<DemoAssets>>,
+    level_lists: Res<Assets<LevelList>>,
+    screen: Res<State<Screen>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut transition_query: Query<&mut LevelTransition>,
+) {
+    let Some(assets) = assets else {
+        warn!("Can't load a level before the level list has been loaded.");
+        return;
+    };
+    let Some(list) = level_lists.get(&assets.levels) else {
+        return;
+    };
+
+    let index = match trigger.event() {
+        LoadLevel::Next => (current_level.0 + 1) % list.handles.len().max(1),
+        LoadLevel::Restart => current_level.0,
+        LoadLevel::Id(id) => {
+            let Some(index) = list.index_of(id) else {
+                warn!("There is no level with id {id:?}.");
+                return;
+            };
+            index
+        }
+    };
+    current_level.0 = index;
+
+    if *screen.get() != Screen::Gameplay {
+        return;
+    }
+    match transition_query.iter_mut().next() {
+        // A fade out will switch to whatever the current level is by then, but
+        // a fade in has to go back to black first.
+        Some(mut transition) => transition.fade_out(),
+        None => {
+            commands.spawn(LevelTransition::bundle());
+        }
+    }
+}
+
+fn spawn_current_level(mut commands: Commands, store: Store, current_level: Res<CurrentLevel>) {
+    Level::spawn(&mut commands, &store, current_level.0);
+    commands.trigger(LevelSpawned);
+}
+
+fn finish_switching(mut next_level_state: ResMut<NextState<LevelState>>) {
+    next_level_state.set(LevelState::Playing);
+}
+
+fn reset_progress(mut current_level: ResMut<CurrentLevel>, mut play_time: ResMut<PlayTime>) {
+    *current_level = default();
+    *play_time = default();
+}
+
+fn tick_play_time(time: Res<Time>, mut play_time: ResMut<PlayTime>) {
+    play_time.0 += time.delta();
+}
+
+const FADE_DURATION_SECS: f32 = 0.4;
+
+/// A full-screen overlay that fades out, switches the level, and fades back in.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct LevelTransition {
+    timer: Timer,
+    fading_out: bool,
+}
+
+impl LevelTransition {
+    fn bundle() -> impl Bundle {
+        (
+            Name::new("Level Transition"),
+            LevelTransition {
+                timer: Timer::from_seconds(FADE_DURATION_SECS, TimerMode::Once),
+                fading_out: true,
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            GlobalZIndex(1),
+            BackgroundColor(Color::BLACK.with_alpha(0.0)),
+            Pickable::IGNORE,
+            StateScoped(Screen::Gameplay),
+        )
+    }
+
+    fn alpha(&self) -> f32 {
+        let t = self.timer.fraction();
+        if self.fading_out { t } else { 1.0 - t }
+    }
+
+    /// Turn a fade in around, continuing from the current alpha.
+    fn fade_out(&mut self) {
+        if self.fading_out {
+            return;
+        }
+        let alpha = self.alpha();
+        self.fading_out = true;
+        let duration = self.timer.duration();
+        self.timer.set_elapsed(duration.mul_f32(alpha));
+    }
+}
+
+fn tick_level_transition(time: Res<Time>, mut transition_query: Query<&mut LevelTransition>) {
+    for mut transition in &mut transition_query {
+        transition.timer.tick(time.delta());
+    }
+}
+
+fn apply_level_transition(
+    mut commands: Commands,
+    level_state: Res<State<LevelState>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    mut transition_query: Query<(Entity, &mut LevelTransition, &mut BackgroundColor)>,
+) {
+    for (entity, mut transition, mut background) in &mut transition_query {
+        background.0.set_alpha(transition.alpha());
+        // Wait for the previous switch to finish before starting another one.
+        if !transition.timer.finished() || *level_state.get() != LevelState::Playing {
+            continue;
+        }
+
+        if transition.fading_out {
+            // The screen is black, so swap the levels and fade back in.
+            next_level_state.set(LevelState::Switching);
+            transition.fading_out = false;
+            transition.timer.reset();
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Loads another level
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "level")]
+struct LevelCommand {
+    /// "next", "restart", or the id of a level
+    target: String,
+}
+
+impl LevelCommand {
+    fn driver(mut log: ConsoleCommand<LevelCommand>, mut commands: Commands) {
+        if let Some(Ok(LevelCommand { target })) = log.take() {
+            let request = match target.as_str() {
+                "next" => LoadLevel::Next,
+                "restart" => LoadLevel::Restart,
+                id => LoadLevel::Id(id.to_string()),
+            };
+            reply!(log, "Loading level: {request:?}");
+            commands.trigger(request);
+        }
+    }
+}
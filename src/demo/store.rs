@@ -4,12 +4,19 @@ use bevy::{
     prelude::*,
 };
 
-use crate::asset_tracking::LoadResource;
+use crate::{
+    animation::AnimationLibrary,
+    asset_tracking::LoadResource,
+    demo::{level::LevelDescription, progression::LevelList, tilemap::MapDescription},
+};
 
 #[derive(SystemParam)]
 pub struct Store<'w> {
     pub assets: Res<'w, DemoAssets>,
-    pub texture_atlas_layouts: ResMut<'w, Assets<TextureAtlasLayout>>,
+    pub animation_libraries: Res<'w, Assets<AnimationLibrary>>,
+    pub levels: Res<'w, Assets<LevelDescription>>,
+    pub level_lists: Res<'w, Assets<LevelList>>,
+    pub maps: Res<'w, Assets<MapDescription>>,
 }
 
 impl<'w> Store<'w> {
@@ -32,9 +39,11 @@ pub struct DemoAssets {
     #[dependency]
     pub ducky: Handle<Image>,
     #[dependency]
+    pub ducky_animations: Handle<AnimationLibrary>,
+    #[dependency]
     pub steps: Vec<Handle<AudioSource>>,
     #[dependency]
-    pub music: Handle<AudioSource>,
+    pub levels: Handle<LevelList>,
 }
 
 impl FromWorld for DemoAssets {
@@ -48,13 +57,14 @@ impl FromWorld for DemoAssets {
                     settings.sampler = ImageSampler::nearest();
                 },
             ),
+            ducky_animations: assets.load("animations/ducky.anim.ron"),
             steps: vec![
                 assets.load("audio/sound_effects/step1.ogg"),
                 assets.load("audio/sound_effects/step2.ogg"),
                 assets.load("audio/sound_effects/step3.ogg"),
                 assets.load("audio/sound_effects/step4.ogg"),
             ],
-            music: assets.load("audio/music/Fluffing A Duck.ogg"),
+            levels: assets.load("levels/campaign.levels.ron"),
         }
     }
 }
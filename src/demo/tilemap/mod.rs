@@ -0,0 +1,445 @@
+//! Tile-based maps.
+//!
+//! A [`MapDescription`] is a grid of tile indices into a tileset image, along
+//! with [`TileFlags`] for each kind of tile. Maps can be written by hand as
+//! `*.map.ron` text grids (see the [`ron`] module), or made in
+//! [Tiled](https://www.mapeditor.org/) and saved as `*.tmx` files (see the
+//! [`tmx`] module).
+//!
+//! Spawning a map creates a [`Tilemap`], which renders the tiles as sprites
+//! grouped into chunks, and stops [`MovementController`]s with a [`Collider`]
+//! from walking through solid tiles.
+
+mod ron;
+mod tmx;
+
+use std::collections::HashMap;
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::demo::{
+    collision::{Collider, resolve_collisions},
+    movement::{
+        MovementController, MovementSystems, PhysicalTranslation, PreviousPhysicalTranslation,
+        Velocity, apply_movement,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Tilemap>();
+    app.init_asset::<MapDescription>();
+    app.register_asset_loader(ron::RonMapLoader);
+    app.register_asset_loader(tmx::TmxMapLoader);
+
+    app.add_systems(
+        FixedUpdate,
+        (
+            slow_down_in_water
+                .in_set(MovementSystems::Move)
+                .after(apply_movement),
+            // Tiles have the final say, so nothing gets pushed into a wall.
+            collide_with_tiles
+                .in_set(MovementSystems::Collide)
+                .after(resolve_collisions),
+        ),
+    );
+}
+
+/// The number of tiles along each side of a chunk.
+const CHUNK_SIZE: u32 = 16;
+
+/// How much slower movement is while standing in water.
+const WATER_SPEED_FACTOR: f32 = 0.5;
+
+/// A loaded map, ready to be spawned.
+#[derive(Asset, TypePath, Debug)]
+pub struct MapDescription {
+    /// The number of columns and rows.
+    pub size: UVec2,
+    /// The size of a tile, in world units before it's scaled by the level's
+    /// [`LevelDescription::tile_scale`].
+    ///
+    /// [`LevelDescription::tile_scale`]: crate::demo::level::LevelDescription::tile_scale
+    pub tile_size: Vec2,
+    /// Layers of tile indices into the tileset, drawn on top of each other.
+    /// Each layer is stored row by row, starting with the top row.
+    pub layers: Vec<Vec<Option<usize>>>,
+    /// The flags of each kind of tile, by tileset index.
+    /// Tiles without an entry have no flags.
+    pub flags: HashMap<usize, TileFlags>,
+    #[dependency]
+    pub tileset: Handle<Image>,
+    pub atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+/// Gameplay properties of a kind of tile.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TileFlags {
+    /// Blocks movement.
+    pub solid: bool,
+    /// Slows down movement.
+    pub water: bool,
+}
+
+impl TileFlags {
+    fn union(self, other: Self) -> Self {
+        Self {
+            solid: self.solid || other.solid,
+            water: self.water || other.water,
+        }
+    }
+}
+
+/// The grid of a spawned map, centered on its entity.
+/// Holds the combined [`TileFlags`] of all layers in each cell.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct Tilemap {
+    pub size: UVec2,
+    pub tile_size: Vec2,
+    cells: Vec<TileFlags>,
+}
+
+impl Tilemap {
+    /// Spawn the map with all of its tiles as a child of `parent`, with its
+    /// tiles scaled by `tile_scale`.
+    pub fn spawn(parent: &mut EntityCommands, map: &MapDescription, tile_scale: f32) {
+        let cells = (0..map.size.element_product() as usize)
+            .map(|cell| {
+                map.layers
+                    .iter()
+                    .filter_map(|layer| layer.get(cell).copied().flatten())
+                    .filter_map(|index| map.flags.get(&index).copied())
+                    .fold(TileFlags::default(), TileFlags::union)
+            })
+            .collect();
+        let tilemap = Tilemap {
+            size: map.size,
+            tile_size: map.tile_size * tile_scale,
+            cells,
+        };
+
+        parent.with_children(|parent| {
+            parent
+                .spawn((
+                    Name::new("Tilemap"),
+                    Transform::default(),
+                    Visibility::default(),
+                    tilemap.clone(),
+                ))
+                .with_children(|parent| {
+                    for (z, layer) in map.layers.iter().enumerate() {
+                        for chunk_y in (0..map.size.y).step_by(CHUNK_SIZE as usize) {
+                            for chunk_x in (0..map.size.x).step_by(CHUNK_SIZE as usize) {
+                                let chunk = UVec2::new(chunk_x, chunk_y);
+                                parent.spawn(tilemap.chunk(map, layer, chunk, z));
+                            }
+                        }
+                    }
+                });
+        });
+    }
+
+    /// A chunk of tile sprites starting at the `first` cell.
+    fn chunk(
+        &self,
+        map: &MapDescription,
+        layer: &[Option<usize>],
+        first: UVec2,
+        z: usize,
+    ) -> impl Bundle {
+        let last = (first + CHUNK_SIZE).min(self.size);
+        let origin = self.cell_min(first) + Vec2::new(0.0, self.tile_size.y);
+
+        let mut tiles = Vec::new();
+        for y in first.y..last.y {
+            for x in first.x..last.x {
+                let cell = UVec2::new(x, y);
+                let Some(index) = layer.get(self.cell_index(cell)).copied().flatten() else {
+                    continue;
+                };
+                let center = self.cell_min(cell) + self.tile_size / 2.0 - origin;
+                tiles.push((
+                    Sprite {
+                        image: map.tileset.clone(),
+                        texture_atlas: Some(TextureAtlas {
+                            layout: map.atlas_layout.clone(),
+                            index,
+                        }),
+                        custom_size: Some(self.tile_size),
+                        ..default()
+                    },
+                    Transform::from_translation(center.extend(0.0)),
+                ));
+            }
+        }
+
+        (
+            Name::new(format!("Tile Chunk ({}, {}) Layer {z}", first.x, first.y)),
+            // Draw the map behind everything else, in layer order.
+            Transform::from_translation(origin.extend(-10.0 + z as f32 * 0.1)),
+            Visibility::default(),
+            Children::spawn(SpawnIter(tiles.into_iter())),
+        )
+    }
+
+    fn half_extent(&self) -> Vec2 {
+        self.size.as_vec2() * self.tile_size / 2.0
+    }
+
+    fn cell_index(&self, cell: UVec2) -> usize {
+        (cell.y * self.size.x + cell.x) as usize
+    }
+
+    /// The bottom left corner of a cell, relative to the center of the map.
+    fn cell_min(&self, cell: UVec2) -> Vec2 {
+        let half = self.half_extent();
+        Vec2::new(
+            -half.x + cell.x as f32 * self.tile_size.x,
+            half.y - (cell.y + 1) as f32 * self.tile_size.y,
+        )
+    }
+
+    /// The column and row at a position relative to the center of the map.
+    /// The row is counted from the top. Positions outside of the map give
+    /// cells outside of [`Self::size`].
+    fn cell_at(&self, position: Vec2) -> IVec2 {
+        let half = self.half_extent();
+        let from_top_left = Vec2::new(position.x + half.x, half.y - position.y);
+        (from_top_left / self.tile_size).floor().as_ivec2()
+    }
+
+    /// The flags of the tiles at a position relative to the center of the map.
+    pub fn flags_at(&self, position: Vec2) -> TileFlags {
+        let cell = self.cell_at(position);
+        if cell.cmplt(IVec2::ZERO).any() || cell.cmpge(self.size.as_ivec2()).any() {
+            return TileFlags::default();
+        }
+        self.cells[self.cell_index(cell.as_uvec2())]
+    }
+
+    /// Move a box along a single axis until it hits a solid tile.
+    /// Returns the new position on that axis.
+    fn sweep(&self, position: Vec2, half_size: Vec2, delta: f32, axis: usize) -> f32 {
+        let mut target = position[axis] + delta;
+        if delta == 0.0 {
+            return target;
+        }
+
+        // Check every cell along the way, so fast movers can't skip over thin walls.
+        // Shrink the box a little, so that touching a wall doesn't count as overlapping it.
+        let mut min = position - half_size + 0.01;
+        let mut max = position + half_size - 0.01;
+        if delta > 0.0 {
+            max[axis] += delta;
+        } else {
+            min[axis] += delta;
+        }
+        let first = self.cell_at(Vec2::new(min.x, max.y)).max(IVec2::ZERO);
+        let last = self
+            .cell_at(Vec2::new(max.x, min.y))
+            .min(self.size.as_ivec2() - 1);
+
+        for y in first.y..=last.y {
+            for x in first.x..=last.x {
+                let cell = UVec2::new(x as u32, y as u32);
+                if !self.cells[self.cell_index(cell)].solid {
+                    continue;
+                }
+                // Tiles that the box already overlaps are ignored, so it can get out of them.
+                let cell_min = self.cell_min(cell);
+                let cell_max = cell_min + self.tile_size;
+                if delta > 0.0 {
+                    let stop = cell_min[axis] - half_size[axis];
+                    if stop >= position[axis] - 0.01 {
+                        target = target.min(stop);
+                    }
+                } else {
+                    let stop = cell_max[axis] + half_size[axis];
+                    if stop <= position[axis] + 0.01 {
+                        target = target.max(stop);
+                    }
+                }
+            }
+        }
+        target
+    }
+}
+
+/// Slow down this tick's movement of entities that started it in water.
+fn slow_down_in_water(
+    tilemap_query: Query<(&Tilemap, &GlobalTransform)>,
+    mut mover_query: Query<
+        (&mut PhysicalTranslation, &PreviousPhysicalTranslation),
+        With<MovementController>,
+    >,
+) {
+    for (mut translation, previous) in &mut mover_query {
+        let in_water = tilemap_query.iter().any(|(tilemap, map_transform)| {
+            let offset = map_transform.translation().xy();
+            tilemap.flags_at(previous.0 - offset).water
+        });
+        if in_water {
+            translation.0 = previous.0 + (translation.0 - previous.0) * WATER_SPEED_FACTOR;
+        }
+    }
+}
+
+/// Redo this tick's movement one axis at a time, so that entities slide along
+/// solid tiles instead of entering them. Colliders are treated as the smallest
+/// box around them.
+fn collide_with_tiles(
+    tilemap_query: Query<(&Tilemap, &GlobalTransform)>,
+    mut mover_query: Query<
+        (
+            &Collider,
+            &mut PhysicalTranslation,
+            &PreviousPhysicalTranslation,
+            &mut Velocity,
+        ),
+        With<MovementController>,
+    >,
+) {
+    for (collider, mut translation, previous, mut velocity) in &mut mover_query {
+        let half_size = collider.half_extents();
+        let target = translation.0;
+        // Every map can only shorten the movement that the previous ones allowed.
+        for (tilemap, map_transform) in &tilemap_query {
+            let offset = map_transform.translation().xy();
+            let mut position = previous.0 - offset;
+            let delta = translation.0 - previous.0;
+            position.x = tilemap.sweep(position, half_size, delta.x, 0);
+            position.y = tilemap.sweep(position, half_size, delta.y, 1);
+            translation.0 = position + offset;
+        }
+
+        // Stop moving into walls.
+        velocity.0 = Vec2::select(translation.0.cmpeq(target), velocity.0, Vec2::ZERO);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// A map with 10 unit tiles, where `#` is solid and `~` is water.
+    fn test_tilemap(rows: &[&str]) -> Tilemap {
+        let cells = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| TileFlags {
+                solid: c == '#',
+                water: c == '~',
+            })
+            .collect();
+        Tilemap {
+            size: UVec2::new(rows[0].len() as u32, rows.len() as u32),
+            tile_size: Vec2::splat(10.0),
+            cells,
+        }
+    }
+
+    #[test]
+    fn cell_at_counts_rows_from_the_top() {
+        let tilemap = test_tilemap(&["....", "....", "...."]);
+        assert_eq!(tilemap.cell_at(Vec2::new(-15.0, 10.0)), IVec2::new(0, 0));
+        assert_eq!(tilemap.cell_at(Vec2::new(19.9, -14.9)), IVec2::new(3, 2));
+        assert_eq!(tilemap.cell_at(Vec2::ZERO), IVec2::new(2, 1));
+        assert_eq!(tilemap.cell_at(Vec2::new(-25.0, 16.0)), IVec2::new(-1, -1));
+    }
+
+    #[test]
+    fn flags_at_combines_position_and_cells() {
+        let tilemap = test_tilemap(&["#~"]);
+        assert!(tilemap.flags_at(Vec2::new(-5.0, 0.0)).solid);
+        assert!(tilemap.flags_at(Vec2::new(5.0, 0.0)).water);
+        assert_eq!(tilemap.flags_at(Vec2::new(15.0, 0.0)), TileFlags::default());
+    }
+
+    #[test]
+    fn sweep_stops_at_walls() {
+        // The wall spans x from -10 to 0.
+        let tilemap = test_tilemap(&["..#..."]);
+        let half_size = Vec2::splat(2.0);
+        assert_eq!(
+            tilemap.sweep(Vec2::new(-25.0, 0.0), half_size, 5.0, 0),
+            -20.0
+        );
+        assert_eq!(
+            tilemap.sweep(Vec2::new(-25.0, 0.0), half_size, 20.0, 0),
+            -12.0
+        );
+        assert_eq!(
+            tilemap.sweep(Vec2::new(25.0, 0.0), half_size, -30.0, 0),
+            2.0
+        );
+        // Moving along the wall isn't blocked.
+        assert_eq!(tilemap.sweep(Vec2::new(-12.0, 0.0), half_size, 1.0, 1), 1.0);
+    }
+
+    #[test]
+    fn sweep_does_not_tunnel_through_thin_walls() {
+        let tilemap = test_tilemap(&["..#..."]);
+        let half_size = Vec2::splat(2.0);
+        // The destination is past the wall, in an empty cell.
+        assert_eq!(
+            tilemap.sweep(Vec2::new(-25.0, 0.0), half_size, 40.0, 0),
+            -12.0
+        );
+        assert_eq!(
+            tilemap.sweep(Vec2::new(25.0, 0.0), half_size, -40.0, 0),
+            2.0
+        );
+
+        // The wall spans y from -5 to 5.
+        let tilemap = test_tilemap(&[".", "#", "."]);
+        assert_eq!(
+            tilemap.sweep(Vec2::new(0.0, -12.0), half_size, 30.0, 1),
+            -7.0
+        );
+    }
+
+    #[test]
+    fn sweep_lets_boxes_out_of_walls() {
+        let tilemap = test_tilemap(&["..#..."]);
+        let half_size = Vec2::splat(2.0);
+        assert_eq!(tilemap.sweep(Vec2::new(-5.0, 0.0), half_size, 10.0, 0), 5.0);
+        assert_eq!(
+            tilemap.sweep(Vec2::new(-5.0, 0.0), half_size, -10.0, 0),
+            -15.0
+        );
+    }
+
+    #[test]
+    fn every_tilemap_limits_movement() {
+        let mut world = World::new();
+        // A wall from x = -10 to 0.
+        world.spawn((test_tilemap(&["..#..."; 6]), GlobalTransform::IDENTITY));
+        // A floor from y = 0 to 10.
+        world.spawn((
+            test_tilemap(&["......", "......", "######", "......", "......", "......"]),
+            GlobalTransform::IDENTITY,
+        ));
+        let mover = world
+            .spawn((
+                MovementController::default(),
+                Collider::Aabb {
+                    half_size: Vec2::splat(2.0),
+                },
+                PreviousPhysicalTranslation(Vec2::new(-25.0, -25.0)),
+                PhysicalTranslation(Vec2::new(15.0, 15.0)),
+                Velocity(Vec2::new(40.0, 40.0)),
+            ))
+            .id();
+
+        world.run_system_once(collide_with_tiles).unwrap();
+
+        let translation = world.get::<PhysicalTranslation>(mover).unwrap();
+        assert_eq!(translation.0, Vec2::new(-12.0, -2.0));
+        assert_eq!(world.get::<Velocity>(mover).unwrap().0, Vec2::ZERO);
+    }
+}
@@ -0,0 +1,162 @@
+//! Maps written by hand as text grids in `*.map.ron` files.
+//!
+//! Every character in `rows` is looked up in the `legend` to find its tileset
+//! index. Spaces are left empty.
+
+use std::{collections::HashMap, error::Error};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    image::{ImageLoaderSettings, ImageSampler},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    animation::AtlasGrid,
+    demo::tilemap::{MapDescription, TileFlags},
+};
+
+#[derive(Deserialize)]
+struct RonMap {
+    tileset: RonTileset,
+    /// The size of a tile in world units.
+    tile_size: Vec2,
+    legend: HashMap<char, usize>,
+    rows: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RonTileset {
+    image: String,
+    grid: AtlasGrid,
+    #[serde(default)]
+    flags: HashMap<usize, TileFlags>,
+}
+
+impl RonMap {
+    /// The size of the map and its tiles, row by row. Short rows are padded
+    /// with empty tiles.
+    fn grid(&self) -> Result<(UVec2, Vec<Option<usize>>), Box<dyn Error + Send + Sync>> {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or_default();
+        let mut tiles = Vec::with_capacity(width * self.rows.len());
+        for (y, row) in self.rows.iter().enumerate() {
+            let mut chars = row.chars();
+            for x in 0..width {
+                tiles.push(match chars.next() {
+                    None | Some(' ') => None,
+                    Some(c) => Some(
+                        *self
+                            .legend
+                            .get(&c)
+                            .ok_or_else(|| format!("unknown tile {c:?} in row {y}, column {x}"))?,
+                    ),
+                });
+            }
+        }
+        Ok((UVec2::new(width as u32, self.rows.len() as u32), tiles))
+    }
+}
+
+pub(super) struct RonMapLoader;
+
+impl AssetLoader for RonMapLoader {
+    type Asset = MapDescription;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let map = ::ron::de::from_bytes::<RonMap>(&bytes)?;
+        let (size, tiles) = map.grid()?;
+
+        Ok(MapDescription {
+            size,
+            tile_size: map.tile_size,
+            layers: vec![tiles],
+            flags: map.tileset.flags,
+            tileset: load_context
+                .loader()
+                .with_settings(|settings: &mut ImageLoaderSettings| {
+                    // Use `nearest` image sampling to preserve pixel art style.
+                    settings.sampler = ImageSampler::nearest();
+                })
+                .load(map.tileset.image),
+            atlas_layout: load_context
+                .add_labeled_asset("layout".to_string(), map.tileset.grid.to_layout()),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(rows: &str) -> RonMap {
+        ::ron::from_str(&format!(
+            r#"(
+                tileset: (
+                    image: "tileset.png",
+                    grid: (tile_size: (16, 16), columns: 4, rows: 1),
+                    flags: {{ 1: (solid: true), 2: (water: true) }},
+                ),
+                tile_size: (64.0, 64.0),
+                legend: {{ '.': 0, '#': 1, '~': 2 }},
+                rows: {rows},
+            )"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_tileset_and_flags() {
+        let map = parse(r##"["#"]"##);
+        assert_eq!(map.tile_size, Vec2::splat(64.0));
+        assert_eq!(map.tileset.image, "tileset.png");
+        assert_eq!(map.tileset.grid.columns, 4);
+        assert_eq!(
+            map.tileset.flags.get(&1),
+            Some(&TileFlags {
+                solid: true,
+                water: false
+            })
+        );
+        assert_eq!(map.tileset.flags.get(&0), None);
+    }
+
+    #[test]
+    fn grid_pads_short_rows() {
+        let (size, tiles) = parse(r##"["#.~", "# ", ""]"##).grid().unwrap();
+        assert_eq!(size, UVec2::new(3, 3));
+        assert_eq!(
+            tiles,
+            [
+                [Some(1), Some(0), Some(2)],
+                [Some(1), None, None],
+                [None, None, None]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn grid_rejects_unknown_tiles() {
+        let error = parse(r#"["..", ".x"]"#).grid().unwrap_err();
+        assert_eq!(error.to_string(), "unknown tile 'x' in row 1, column 1");
+    }
+}
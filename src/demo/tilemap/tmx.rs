@@ -0,0 +1,266 @@
+//! Maps made in [Tiled](https://www.mapeditor.org/) and saved as `*.tmx` files.
+//!
+//! Only the features needed by the demo are supported: orthogonal maps with a
+//! single tileset embedded in the map, and tile layers in the CSV encoding.
+//! Boolean tile properties named `solid` or `water` become [`TileFlags`].
+//! Flipped and rotated tiles are drawn without their transformation.
+
+use std::{collections::HashMap, error::Error};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    image::{ImageLoaderSettings, ImageSampler},
+    prelude::*,
+};
+use roxmltree::{Document, Node};
+
+use crate::demo::tilemap::{MapDescription, TileFlags};
+
+/// Tiled stores flip and rotation flags in the highest bits of a tile id.
+const TILE_ID_MASK: u32 = 0x0fff_ffff;
+
+pub(super) struct TmxMapLoader;
+
+impl AssetLoader for TmxMapLoader {
+    type Asset = MapDescription;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let map = TmxMap::parse(&String::from_utf8(bytes)?)?;
+        let image_path = load_context.asset_path().resolve_embed(&map.image)?;
+
+        Ok(MapDescription {
+            size: map.size,
+            tile_size: map.tile_size.as_vec2(),
+            layers: map.layers,
+            flags: map.flags,
+            tileset: load_context
+                .loader()
+                .with_settings(|settings: &mut ImageLoaderSettings| {
+                    // Use `nearest` image sampling to preserve pixel art style.
+                    settings.sampler = ImageSampler::nearest();
+                })
+                .load(image_path),
+            atlas_layout: load_context.add_labeled_asset("layout".to_string(), map.layout),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+/// The parts of a `*.tmx` file that make up a [`MapDescription`].
+#[derive(Debug)]
+struct TmxMap {
+    size: UVec2,
+    /// The size of a tile in pixels.
+    tile_size: UVec2,
+    layers: Vec<Vec<Option<usize>>>,
+    flags: HashMap<usize, TileFlags>,
+    /// The path of the tileset image, relative to the map.
+    image: String,
+    layout: TextureAtlasLayout,
+}
+
+impl TmxMap {
+    fn parse(text: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let document = Document::parse(text)?;
+        let map = document.root_element();
+
+        if map.attribute("orientation") != Some("orthogonal") {
+            return Err("only orthogonal maps are supported".into());
+        }
+        let size = UVec2::new(attribute(map, "width")?, attribute(map, "height")?);
+        let tile_size = UVec2::new(attribute(map, "tilewidth")?, attribute(map, "tileheight")?);
+
+        let tileset = child(map, "tileset")?;
+        if tileset.has_attribute("source") {
+            return Err("external tilesets are not supported, embed the tileset in the map".into());
+        }
+        let first_id: u32 = attribute(tileset, "firstgid")?;
+        let image = child(tileset, "image")?
+            .attribute("source")
+            .ok_or("tileset image has no source")?
+            .to_string();
+
+        let tile_count = size.element_product() as usize;
+        let mut layers = Vec::new();
+        for layer in map.children().filter(|node| node.has_tag_name("layer")) {
+            let data = child(layer, "data")?;
+            if data.attribute("encoding") != Some("csv") {
+                return Err("only CSV encoded layers are supported".into());
+            }
+            let mut tiles = data
+                .text()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| -> Result<_, Box<dyn Error + Send + Sync>> {
+                    let id = id.parse::<u32>()? & TILE_ID_MASK;
+                    // Id 0 is an empty tile.
+                    Ok((id >= first_id).then(|| (id - first_id) as usize))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // A layer without data has no tiles.
+            if tiles.is_empty() {
+                tiles = vec![None; tile_count];
+            }
+            if tiles.len() != tile_count {
+                return Err(
+                    format!("layer has {} tiles instead of {tile_count}", tiles.len()).into(),
+                );
+            }
+            layers.push(tiles);
+        }
+
+        let mut flags = HashMap::new();
+        for tile in tileset.children().filter(|node| node.has_tag_name("tile")) {
+            let index = attribute(tile, "id")?;
+            let mut tile_flags = TileFlags::default();
+            let properties = tile
+                .children()
+                .filter(|node| node.has_tag_name("properties"))
+                .flat_map(|properties| properties.children())
+                .filter(|node| node.has_tag_name("property"));
+            for property in properties {
+                let value = property.attribute("value") == Some("true");
+                match property.attribute("name") {
+                    Some("solid") => tile_flags.solid = value,
+                    Some("water") => tile_flags.water = value,
+                    _ => {}
+                }
+            }
+            flags.insert(index, tile_flags);
+        }
+
+        let layout = TextureAtlasLayout::from_grid(
+            tile_size,
+            attribute(tileset, "columns")?,
+            attribute::<u32>(tileset, "tilecount")?.div_ceil(attribute(tileset, "columns")?),
+            tileset
+                .attribute("spacing")
+                .map(|spacing| spacing.parse().map(UVec2::splat))
+                .transpose()?,
+            tileset
+                .attribute("margin")
+                .map(|margin| margin.parse().map(UVec2::splat))
+                .transpose()?,
+        );
+
+        Ok(Self {
+            size,
+            tile_size,
+            layers,
+            flags,
+            image,
+            layout,
+        })
+    }
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &str,
+) -> Result<Node<'a, 'input>, Box<dyn Error + Send + Sync>> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .ok_or_else(|| format!("<{}> has no <{name}>", node.tag_name().name()).into())
+}
+
+fn attribute<T>(node: Node, name: &str) -> Result<T, Box<dyn Error + Send + Sync>>
+where
+    T: std::str::FromStr,
+    T::Err: Error + Send + Sync + 'static,
+{
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| format!("<{}> has no {name}", node.tag_name().name()))?;
+    Ok(value.parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(data: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="tileset" tilewidth="16" tileheight="16" tilecount="4" columns="4">
+  <image source="../images/tileset.png" width="64" height="16"/>
+  <tile id="1">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+  </tile>
+  <tile id="2">
+   <properties>
+    <property name="water" type="bool" value="true"/>
+    <property name="other" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="3" height="2">
+  <data encoding="csv">{data}</data>
+ </layer>
+</map>"#
+        )
+    }
+
+    #[test]
+    fn parses_map() {
+        let map = TmxMap::parse(&map("\n1,2,3,\n0,4,2147483650\n")).unwrap();
+        assert_eq!(map.size, UVec2::new(3, 2));
+        assert_eq!(map.tile_size, UVec2::splat(16));
+        assert_eq!(map.image, "../images/tileset.png");
+        // Flipped tiles keep their index.
+        assert_eq!(
+            map.layers,
+            [vec![Some(0), Some(1), Some(2), None, Some(3), Some(1)]]
+        );
+        assert_eq!(
+            map.flags.get(&1),
+            Some(&TileFlags {
+                solid: true,
+                water: false
+            })
+        );
+        assert_eq!(
+            map.flags.get(&2),
+            Some(&TileFlags {
+                solid: false,
+                water: true
+            })
+        );
+        assert_eq!(map.layout.textures.len(), 4);
+    }
+
+    #[test]
+    fn empty_layer_has_no_tiles() {
+        let map = TmxMap::parse(&map("")).unwrap();
+        assert_eq!(map.layers, [vec![None; 6]]);
+    }
+
+    #[test]
+    fn rejects_wrong_tile_count() {
+        let error = TmxMap::parse(&map("1,2,3")).unwrap_err();
+        assert_eq!(error.to_string(), "layer has 3 tiles instead of 6");
+    }
+
+    #[test]
+    fn rejects_other_encodings() {
+        let text = map("AAAA").replace("encoding=\"csv\"", "encoding=\"base64\"");
+        let error = TmxMap::parse(&text).unwrap_err();
+        assert_eq!(error.to_string(), "only CSV encoded layers are supported");
+    }
+}
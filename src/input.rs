@@ -0,0 +1,552 @@
+//! A data-driven action layer on top of raw keyboard, mouse and gamepad input.
+//!
+//! Gameplay and UI systems should read [`ButtonInput<Action>`] instead of raw key
+//! codes, which means the usual run conditions such as `input_just_pressed(Action::Pause)`
+//! work out of the box. Which inputs trigger which [`Action`] is decided by
+//! [`ActionBindings`], whose defaults are loaded from `assets/input/default.bindings.ron`
+//! so every game built on this template can ship its own. The built-in bindings are
+//! used until the file has loaded, or if it can't be loaded, and changes to the file
+//! are applied right away when hot reloading.
+//!
+//! Directional movement is also combined into a single analog [`MoveAxis`], so
+//! keyboard, D-pad and analog stick input can be mixed freely.
+//!
+//! While the console is open, typing into it shouldn't control the game, so all
+//! actions are released and [`MoveAxis`] is zero, see [`input_enabled`]. Once it
+//! closes, actions stay released until their bindings have been let go of, so a
+//! key that is still held from typing doesn't trigger anything.
+//! Systems that read raw input instead of actions should use the same run condition.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::{asset::AssetLoadFailedEvent, input::InputSystem, prelude::*};
+use bevy_console::ConsoleOpen;
+use serde::{Deserialize, Serialize};
+
+use crate::ron_asset::{InitRonAsset, RonAsset};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Action>();
+    app.register_type::<ActionBindings>();
+    app.register_type::<MoveAxis>();
+    app.init_resource::<ButtonInput<Action>>();
+    app.init_resource::<MoveAxis>();
+    app.init_resource::<PendingRelease>();
+
+    // Use the built-in bindings until the bindings file has been loaded.
+    app.init_resource::<ActionBindings>();
+    app.init_ron_asset::<ActionBindings>();
+    let handle = app
+        .world()
+        .resource::<AssetServer>()
+        .load("input/default.bindings.ron");
+    app.insert_resource(ActionBindingsHandle(handle));
+
+    app.add_systems(
+        PreUpdate,
+        (
+            (apply_loaded_bindings, report_failed_bindings),
+            (
+                (update_actions, update_move_axis)
+                    .chain()
+                    .run_if(input_enabled),
+                release_input.run_if(not(input_enabled)),
+            ),
+        )
+            .chain()
+            .after(InputSystem),
+    );
+}
+
+/// Something the player can do, independent of the device used to do it.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Sprint,
+    Pause,
+    Back,
+    ToggleConsole,
+    ToggleDebugUi,
+    ToggleDiagnostics,
+}
+
+/// A single physical input.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+/// Maps every [`Action`] to the inputs that trigger it.
+#[derive(Resource, Asset, Reflect, Serialize, Deserialize, Clone, Debug)]
+#[reflect(Resource)]
+pub struct ActionBindings {
+    pub actions: HashMap<Action, Vec<Binding>>,
+    /// How the left stick of a gamepad is turned into [`MoveAxis`].
+    #[serde(default)]
+    pub move_stick: StickSettings,
+}
+
+/// Dead zones and response curve of an analog stick.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct StickSettings {
+    /// Deflections below this magnitude are ignored, to hide stick drift.
+    pub inner_dead_zone: f32,
+    /// Deflections above this magnitude count as fully deflected.
+    pub outer_dead_zone: f32,
+    /// How the deflection between the dead zones maps to the output magnitude.
+    pub curve: ResponseCurve,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            inner_dead_zone: 0.15,
+            outer_dead_zone: 0.95,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl StickSettings {
+    /// Apply the dead zones and response curve to a raw stick position.
+    /// The result has a length between 0 and 1.
+    pub fn apply(&self, stick: Vec2) -> Vec2 {
+        let magnitude = stick.length();
+        if magnitude <= self.inner_dead_zone {
+            return Vec2::ZERO;
+        }
+        let range = (self.outer_dead_zone - self.inner_dead_zone).max(f32::EPSILON);
+        let t = ((magnitude - self.inner_dead_zone) / range).clamp(0.0, 1.0);
+        stick / magnitude * self.curve.apply(t)
+    }
+}
+
+/// Maps a stick deflection between 0 and 1 to an output between 0 and 1.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ResponseCurve {
+    Linear,
+    /// Finer control near the center of the stick.
+    Quadratic,
+    /// Even finer control near the center of the stick.
+    Cubic,
+    /// Raise the deflection to an arbitrary power.
+    Power(f32),
+}
+
+impl ResponseCurve {
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::Quadratic => t * t,
+            Self::Cubic => t * t * t,
+            Self::Power(exponent) => t.powf(exponent),
+        }
+    }
+}
+
+/// The direction the player wants to move in, with a length between 0 and 1.
+/// Digital input (keys, D-pad) and analog input (sticks) are merged, so the
+/// player can switch devices at any time.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, Deref)]
+#[reflect(Resource)]
+pub struct MoveAxis(pub Vec2);
+
+impl RonAsset for ActionBindings {
+    const EXTENSIONS: &'static [&'static str] = &["bindings.ron"];
+}
+
+/// The same bindings as `assets/input/default.bindings.ron`, so input works even if
+/// the file is missing or broken.
+impl Default for ActionBindings {
+    fn default() -> Self {
+        use Binding::*;
+        Self {
+            actions: HashMap::from([
+                (
+                    Action::MoveUp,
+                    vec![
+                        Key(KeyCode::KeyW),
+                        Key(KeyCode::ArrowUp),
+                        Gamepad(GamepadButton::DPadUp),
+                    ],
+                ),
+                (
+                    Action::MoveDown,
+                    vec![
+                        Key(KeyCode::KeyS),
+                        Key(KeyCode::ArrowDown),
+                        Gamepad(GamepadButton::DPadDown),
+                    ],
+                ),
+                (
+                    Action::MoveLeft,
+                    vec![
+                        Key(KeyCode::KeyA),
+                        Key(KeyCode::ArrowLeft),
+                        Gamepad(GamepadButton::DPadLeft),
+                    ],
+                ),
+                (
+                    Action::MoveRight,
+                    vec![
+                        Key(KeyCode::KeyD),
+                        Key(KeyCode::ArrowRight),
+                        Gamepad(GamepadButton::DPadRight),
+                    ],
+                ),
+                (
+                    Action::Dash,
+                    vec![Key(KeyCode::Space), Gamepad(GamepadButton::South)],
+                ),
+                (
+                    Action::Sprint,
+                    vec![
+                        Key(KeyCode::ShiftLeft),
+                        Gamepad(GamepadButton::RightTrigger2),
+                    ],
+                ),
+                (
+                    Action::Pause,
+                    vec![Key(KeyCode::KeyP), Gamepad(GamepadButton::Start)],
+                ),
+                (
+                    Action::Back,
+                    vec![Key(KeyCode::Escape), Gamepad(GamepadButton::East)],
+                ),
+                (Action::ToggleConsole, vec![Key(KeyCode::Backquote)]),
+                (Action::ToggleDebugUi, vec![Key(KeyCode::F1)]),
+                (Action::ToggleDiagnostics, vec![Key(KeyCode::F2)]),
+            ]),
+            move_stick: StickSettings {
+                curve: ResponseCurve::Quadratic,
+                ..default()
+            },
+        }
+    }
+}
+
+impl ActionBindings {
+    /// The keys bound to an action, for consumers that only understand the keyboard.
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = KeyCode> + '_ {
+        self.actions
+            .get(&action)
+            .into_iter()
+            .flatten()
+            .filter_map(|binding| match binding {
+                Binding::Key(key) => Some(*key),
+                _ => None,
+            })
+    }
+}
+
+/// The bindings file, which [`ActionBindings`] are copied from whenever it's
+/// (re)loaded.
+#[derive(Resource)]
+struct ActionBindingsHandle(Handle<ActionBindings>);
+
+fn apply_loaded_bindings(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<ActionBindings>>,
+    handle: Res<ActionBindingsHandle>,
+    assets: Res<Assets<ActionBindings>>,
+) {
+    for event in asset_events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        if let Some(bindings) = assets.get(&handle.0) {
+            commands.insert_resource(bindings.clone());
+        }
+    }
+}
+
+fn report_failed_bindings(mut failed_events: EventReader<AssetLoadFailedEvent<ActionBindings>>) {
+    for event in failed_events.read() {
+        error!(
+            "Failed to load the input bindings from {}, keeping the current ones: {}",
+            event.path, event.error
+        );
+    }
+}
+
+/// A run condition that is false while the player is typing into the console.
+pub fn input_enabled(console_open: Option<Res<ConsoleOpen>>) -> bool {
+    !console_open.is_some_and(|console_open| console_open.open)
+}
+
+/// Actions that stay released until none of their bindings are pressed, because
+/// input was disabled while they may have been held.
+#[derive(Resource, Default)]
+struct PendingRelease(HashSet<Action>);
+
+/// Release all actions while input is disabled.
+fn release_input(
+    bindings: Res<ActionBindings>,
+    mut actions: ResMut<ButtonInput<Action>>,
+    mut move_axis: ResMut<MoveAxis>,
+    mut pending_release: ResMut<PendingRelease>,
+) {
+    actions.clear();
+    actions.release_all();
+    move_axis.0 = Vec2::ZERO;
+    pending_release.0.extend(bindings.actions.keys());
+}
+
+/// Press and release actions based on the state of their bindings.
+fn update_actions(
+    bindings: Res<ActionBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut actions: ResMut<ButtonInput<Action>>,
+    mut pending_release: ResMut<PendingRelease>,
+) {
+    actions.clear();
+    for (&action, action_bindings) in &bindings.actions {
+        let pressed = action_bindings.iter().any(|binding| match *binding {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse_buttons.pressed(button),
+            Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+        });
+        if !pressed {
+            pending_release.0.remove(&action);
+        }
+        if pressed && !pending_release.0.contains(&action) {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+
+    // Actions may have been removed from the bindings while they were pressed.
+    let unbound = actions
+        .get_pressed()
+        .filter(|action| !bindings.actions.contains_key(action))
+        .copied()
+        .collect::<Vec<_>>();
+    for action in unbound {
+        actions.release(action);
+    }
+}
+
+/// Combine directional actions and gamepad sticks into a single [`MoveAxis`].
+fn update_move_axis(
+    bindings: Res<ActionBindings>,
+    actions: Res<ButtonInput<Action>>,
+    gamepads: Query<&Gamepad>,
+    mut move_axis: ResMut<MoveAxis>,
+) {
+    let mut digital = Vec2::ZERO;
+    if actions.pressed(Action::MoveUp) {
+        digital.y += 1.0;
+    }
+    if actions.pressed(Action::MoveDown) {
+        digital.y -= 1.0;
+    }
+    if actions.pressed(Action::MoveLeft) {
+        digital.x -= 1.0;
+    }
+    if actions.pressed(Action::MoveRight) {
+        digital.x += 1.0;
+    }
+    // Normalize so that diagonal movement is the same speed as horizontal / vertical.
+    let digital = digital.normalize_or_zero();
+
+    // Use the most deflected stick if multiple gamepads are connected.
+    let analog = gamepads
+        .iter()
+        .map(|gamepad| bindings.move_stick.apply(gamepad.left_stick()))
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or(Vec2::ZERO);
+
+    move_axis.0 = (digital + analog).clamp_length_max(1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{
+        InputPlugin,
+        gamepad::{
+            GamepadConnection, GamepadConnectionEvent, RawGamepadAxisChangedEvent, RawGamepadEvent,
+        },
+    };
+
+    use super::*;
+
+    /// An app with only the input systems and the built-in bindings, and a connected gamepad.
+    fn app_with_gamepad(move_stick: StickSettings) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin));
+        app.init_resource::<ButtonInput<Action>>();
+        app.init_resource::<MoveAxis>();
+        app.init_resource::<PendingRelease>();
+        app.insert_resource(ActionBindings {
+            move_stick,
+            ..default()
+        });
+        app.add_systems(
+            PreUpdate,
+            (update_actions, update_move_axis)
+                .chain()
+                .after(InputSystem),
+        );
+
+        let gamepad = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(GamepadConnectionEvent::new(
+            gamepad,
+            GamepadConnection::Connected {
+                name: "Test gamepad".to_string(),
+                vendor_id: None,
+                product_id: None,
+            },
+        ));
+        app.update();
+        (app, gamepad)
+    }
+
+    fn move_left_stick(app: &mut App, gamepad: Entity, stick: Vec2) {
+        app.world_mut().send_event_batch([
+            RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(
+                gamepad,
+                GamepadAxis::LeftStickX,
+                stick.x,
+            )),
+            RawGamepadEvent::Axis(RawGamepadAxisChangedEvent::new(
+                gamepad,
+                GamepadAxis::LeftStickY,
+                stick.y,
+            )),
+        ]);
+    }
+
+    fn stick_settings(curve: ResponseCurve) -> StickSettings {
+        StickSettings {
+            inner_dead_zone: 0.2,
+            outer_dead_zone: 0.8,
+            curve,
+        }
+    }
+
+    fn move_axis(curve: ResponseCurve, stick: Vec2) -> Vec2 {
+        let (mut app, gamepad) = app_with_gamepad(stick_settings(curve));
+        move_left_stick(&mut app, gamepad, stick);
+        app.update();
+        app.world().resource::<MoveAxis>().0
+    }
+
+    fn assert_move_axis(curve: ResponseCurve, stick: Vec2, expected: Vec2) {
+        let actual = move_axis(curve, stick);
+        assert!(
+            actual.abs_diff_eq(expected, 1e-5),
+            "{curve:?} should map {stick} to {expected}, not {actual}"
+        );
+    }
+
+    #[test]
+    fn built_in_bindings_match_the_bindings_file() {
+        let file: ActionBindings =
+            ron::from_str(include_str!("../assets/input/default.bindings.ron")).unwrap();
+        let built_in = ActionBindings::default();
+        assert_eq!(file.actions, built_in.actions);
+        assert_eq!(file.move_stick, built_in.move_stick);
+    }
+
+    #[test]
+    fn inside_dead_zone_is_ignored() {
+        assert_move_axis(ResponseCurve::Linear, Vec2::new(0.1, 0.1), Vec2::ZERO);
+        assert_move_axis(ResponseCurve::Linear, Vec2::new(0.0, -0.2), Vec2::ZERO);
+    }
+
+    #[test]
+    fn across_dead_zone_is_rescaled() {
+        // Halfway between the dead zones.
+        assert_move_axis(
+            ResponseCurve::Linear,
+            Vec2::new(0.5, 0.0),
+            Vec2::new(0.5, 0.0),
+        );
+        assert_move_axis(
+            ResponseCurve::Linear,
+            Vec2::new(0.0, -0.5),
+            Vec2::new(0.0, -0.5),
+        );
+        // Past the outer dead zone counts as fully deflected.
+        assert_move_axis(
+            ResponseCurve::Linear,
+            Vec2::new(0.9, 0.0),
+            Vec2::new(1.0, 0.0),
+        );
+        assert_move_axis(
+            ResponseCurve::Linear,
+            Vec2::new(-0.9, 0.9),
+            Vec2::new(-1.0, 1.0).normalize(),
+        );
+    }
+
+    #[test]
+    fn keys_and_sticks_are_merged_and_clamped() {
+        let (mut app, gamepad) = app_with_gamepad(stick_settings(ResponseCurve::Linear));
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyD);
+        move_left_stick(&mut app, gamepad, Vec2::new(0.0, 0.5));
+        app.update();
+
+        let actual = app.world().resource::<MoveAxis>().0;
+        let expected = Vec2::new(1.0, 0.5).normalize();
+        assert!(
+            actual.abs_diff_eq(expected, 1e-5),
+            "KeyD and a half deflected stick should move towards {expected}, not {actual}"
+        );
+    }
+
+    #[test]
+    fn keys_held_when_the_console_closes_stay_released() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin, plugin));
+        app.insert_resource(ConsoleOpen { open: true });
+        app.update();
+        let press = |app: &mut App, pressed: bool| {
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            if pressed {
+                keys.press(KeyCode::KeyP);
+            } else {
+                keys.release(KeyCode::KeyP);
+            }
+            app.update();
+            app.world().resource::<ButtonInput<Action>>().clone()
+        };
+
+        // Typing into the console.
+        assert!(!press(&mut app, true).pressed(Action::Pause));
+        // Closing the console while still holding the key.
+        app.world_mut().resource_mut::<ConsoleOpen>().open = false;
+        app.update();
+        let actions = app.world().resource::<ButtonInput<Action>>();
+        assert!(!actions.just_pressed(Action::Pause));
+        assert!(!actions.pressed(Action::Pause));
+
+        assert!(!press(&mut app, false).pressed(Action::Pause));
+        assert!(press(&mut app, true).just_pressed(Action::Pause));
+    }
+
+    #[test]
+    fn response_curves_shape_the_magnitude() {
+        let stick = Vec2::new(0.5, 0.0);
+        assert_move_axis(ResponseCurve::Linear, stick, Vec2::new(0.5, 0.0));
+        assert_move_axis(ResponseCurve::Quadratic, stick, Vec2::new(0.25, 0.0));
+        assert_move_axis(ResponseCurve::Cubic, stick, Vec2::new(0.125, 0.0));
+        assert_move_axis(
+            ResponseCurve::Power(0.5),
+            stick,
+            Vec2::new(0.5_f32.sqrt(), 0.0),
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! A 2D game template for Bevy.
+//!
+//! The game lives in this library, so it can be driven by integration tests
+//! through [`GameplayPlugin`]. The binary only parses the command line and runs
+//! [`AppPlugin`].
+
+// Support configuring Bevy lints within code.
+#![cfg_attr(bevy_lint, feature(register_tool), register_tool(bevy))]
+
+pub mod animation;
+pub mod asset_tracking;
+pub mod audio;
+pub mod camera;
+pub mod cli;
+pub mod debug;
+pub mod demo;
+pub mod input;
+pub mod menus;
+pub mod replay;
+pub mod rng;
+pub mod ron_asset;
+pub mod save;
+pub mod screens;
+pub mod settings;
+pub mod theme;
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    asset::AssetMetaCheck,
+    audio::{AudioLoader, AudioPlugin},
+    log::LogPlugin,
+    prelude::*,
+    render::{RenderPlugin, settings::WgpuSettings},
+    window::{ExitCondition, WindowResolution},
+    winit::WinitPlugin,
+};
+#[cfg(feature = "embed_all_assets")]
+use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
+
+use crate::cli::Cli;
+
+/// The whole game, including Bevy's [`DefaultPlugins`].
+///
+/// The window and audio output can be turned off, to run the game without a
+/// display, GPU or audio device:
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use template_2d::AppPlugin;
+/// App::new().add_plugins(AppPlugin::headless()).run();
+/// ```
+///
+/// This is how the integration tests go through the screens and menus.
+pub struct AppPlugin {
+    /// Open a window and render to it. Without a window, nothing is rendered,
+    /// and [`App::run`] keeps updating until an [`AppExit`] event is sent.
+    pub window: bool,
+    /// Play audio through the default audio device. Audio assets are loaded
+    /// either way.
+    pub audio: bool,
+}
+
+impl Default for AppPlugin {
+    fn default() -> Self {
+        Self {
+            window: true,
+            audio: true,
+        }
+    }
+}
+
+impl AppPlugin {
+    /// Run without a window, rendering or audio output.
+    pub fn headless() -> Self {
+        Self {
+            window: false,
+            audio: false,
+        }
+    }
+}
+
+impl Plugin for AppPlugin {
+    fn build(&self, app: &mut App) {
+        let cli = app.world_mut().get_resource_or_init::<Cli>().clone();
+
+        // Add Bevy plugins.
+        let mut default_plugins = DefaultPlugins
+            .set(AssetPlugin {
+                // Wasm builds will check for meta files (that don't exist) if this isn't set.
+                // This causes errors and even panics on web build on itch.
+                // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
+                meta_check: AssetMetaCheck::Never,
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: self.window.then(|| Window {
+                    title: "Template 2d".to_string(),
+                    fit_canvas_to_parent: true,
+                    resolution: cli
+                        .windowed
+                        .map(|size| WindowResolution::new(size.x as f32, size.y as f32))
+                        .unwrap_or_default(),
+                    ..default()
+                }),
+                exit_condition: if self.window {
+                    ExitCondition::OnPrimaryClosed
+                } else {
+                    ExitCondition::DontExit
+                },
+                ..default()
+            })
+            .set(LogPlugin {
+                // Keep Bevy's default level and filter, which the console can
+                // narrow down further with the `loglevel` command.
+                // Our logs also go to the console and a log file, see `debug::logging`.
+                custom_layer: debug::logging::custom_layer,
+                ..default()
+            });
+        if !self.window {
+            default_plugins = default_plugins
+                .disable::<WinitPlugin>()
+                .set(RenderPlugin {
+                    // Don't look for a GPU.
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .add(ScheduleRunnerPlugin::default());
+        }
+        if !self.audio {
+            default_plugins = default_plugins.disable::<AudioPlugin>();
+        }
+        app.add_plugins((
+            #[cfg(feature = "embed_all_assets")]
+            EmbeddedAssetPlugin {
+                mode: PluginMode::ReplaceDefault,
+            },
+            default_plugins,
+        ));
+        if !self.audio {
+            // Keep loading audio assets, so the game doesn't notice the difference.
+            app.init_asset::<AudioSource>();
+            app.init_asset_loader::<AudioLoader>();
+            app.init_resource::<GlobalVolume>();
+        }
+
+        // Add other plugins.
+        app.add_plugins((
+            GameplayPlugin,
+            audio::plugin,
+            debug::plugin,
+            menus::plugin,
+            screens::plugin,
+            settings::plugin,
+            theme::plugin,
+        ));
+    }
+}
+
+/// The gameplay on its own: screens, levels, the player, input, replays and
+/// saves, but no menus, UI, audio output, settings or debug tools.
+///
+/// This only needs Bevy's [`MinimalPlugins`], along with the [`AssetPlugin`],
+/// [`StatesPlugin`](bevy::state::app::StatesPlugin),
+/// [`InputPlugin`](bevy::input::InputPlugin) and [`ImagePlugin`], which is how
+/// integration tests run the game. Its console commands can be run without the
+/// console, e.g. with `--exec`.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            debug::console::commands_plugin,
+            screens::state_plugin,
+            animation::plugin,
+            asset_tracking::plugin,
+            camera::plugin,
+            cli::plugin,
+            demo::plugin,
+            input::plugin,
+            replay::plugin,
+            rng::plugin,
+            save::plugin,
+        ));
+
+        // Order new `AppSystems` variants by adding them here:
+        app.configure_sets(
+            Update,
+            (
+                AppSystems::TickTimers,
+                AppSystems::RecordInput,
+                AppSystems::Update,
+            )
+                .chain(),
+        );
+
+        // Run gameplay logic at a fixed rate, so it doesn't depend on the frame rate.
+        app.insert_resource(Time::<Fixed>::from_hz(FIXED_TICK_RATE_HZ));
+
+        // Set up the `Pause` state.
+        app.init_state::<Pause>();
+        app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
+        app.configure_sets(FixedUpdate, PausableSystems.run_if(in_state(Pause(false))));
+    }
+}
+
+/// The default number of [`FixedUpdate`] ticks per second.
+/// This can be changed at runtime through [`Time<Fixed>`].
+pub const FIXED_TICK_RATE_HZ: f64 = 64.0;
+
+/// High-level groupings of systems for the app in the `Update` schedule.
+/// When adding a new variant, make sure to order it in the `configure_sets`
+/// call above.
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum AppSystems {
+    /// Tick timers.
+    TickTimers,
+    /// Record player input. Input is released while the console is open, see
+    /// [`input::input_enabled`].
+    RecordInput,
+    /// Do everything else (consider splitting this into further variants).
+    Update,
+}
+
+/// Whether or not the game is paused.
+#[derive(States, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[states(scoped_entities)]
+pub struct Pause(pub bool);
+
+/// A system set for systems that shouldn't run while the game is paused.
+#[derive(SystemSet, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PausableSystems;
@@ -2,31 +2,43 @@
 
 use bevy::prelude::*;
 
-use crate::{asset_tracking::ResourceHandles, menus::Menu, screens::Screen, theme::widget};
+use crate::{
+    asset_tracking::ResourceHandles,
+    menus::Menu,
+    save::{LoadGame, SaveDir},
+    screens::Screen,
+    theme::widget,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Main), spawn_main_menu);
 }
 
-fn spawn_main_menu(mut commands: Commands) {
-    commands.spawn((
+fn spawn_main_menu(mut commands: Commands, save_dir: Res<SaveDir>) {
+    let mut menu = commands.spawn((
         widget::ui_root("Main Menu"),
         GlobalZIndex(2),
         StateScoped(Menu::Main),
-        #[cfg(not(target_family = "wasm"))]
-        children![
-            widget::button("Play", enter_loading_or_gameplay_screen),
-            widget::button("Settings", open_settings_menu),
-            widget::button("Credits", open_credits_menu),
-            widget::button("Exit", exit_app),
-        ],
-        #[cfg(target_family = "wasm")]
-        children![
-            widget::button("Play", enter_loading_or_gameplay_screen),
-            widget::button("Settings", open_settings_menu),
-            widget::button("Credits", open_credits_menu),
-        ],
     ));
+    menu.with_children(|menu| {
+        if let Some(slot) = save_dir.latest_slot() {
+            menu.spawn(widget::button("Continue", continue_game(slot)));
+        }
+        menu.spawn(widget::button("Play", enter_loading_or_gameplay_screen));
+        #[cfg(not(target_family = "wasm"))]
+        menu.spawn(widget::button("Load", open_save_slots_menu));
+        menu.spawn(widget::button("Settings", open_settings_menu));
+        menu.spawn(widget::button("Credits", open_credits_menu));
+        #[cfg(not(target_family = "wasm"))]
+        menu.spawn(widget::button("Exit", exit_app));
+    });
+}
+
+/// Load the most recent save.
+fn continue_game(slot: u32) -> impl Fn(Trigger<Pointer<Click>>, Commands) {
+    move |_, mut commands| {
+        commands.trigger(LoadGame(slot));
+    }
 }
 
 fn enter_loading_or_gameplay_screen(
@@ -41,6 +53,11 @@ fn enter_loading_or_gameplay_screen(
     }
 }
 
+#[cfg(not(target_family = "wasm"))]
+fn open_save_slots_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::SaveSlots);
+}
+
 fn open_settings_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Settings);
 }
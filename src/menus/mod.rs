@@ -3,6 +3,7 @@
 mod credits;
 mod main;
 mod pause;
+mod save_slots;
 mod settings;
 
 use bevy::prelude::*;
@@ -15,6 +16,7 @@ pub(super) fn plugin(app: &mut App) {
         main::plugin,
         settings::plugin,
         pause::plugin,
+        save_slots::plugin,
     ));
 }
 
@@ -27,4 +29,5 @@ pub enum Menu {
     Credits,
     Settings,
     Pause,
+    SaveSlots,
 }
@@ -0,0 +1,287 @@
+//! The save slot menu, for saving from the pause menu and loading from the
+//! main menu.
+//!
+//! Overwriting or deleting a save has to be confirmed first.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    input::Action,
+    menus::Menu,
+    save::{LoadGame, SLOT_COUNT, SaveDir, SaveError, SaveGame, SaveMetadata, SaveResult},
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SaveSlotsMenu>();
+
+    app.add_systems(OnEnter(Menu::SaveSlots), reset_save_slots_menu);
+    app.add_systems(
+        Update,
+        (
+            go_back.run_if(input_just_pressed(Action::Back)),
+            spawn_save_slots_menu.run_if(resource_changed::<SaveSlotsMenu>),
+        )
+            .chain()
+            .run_if(in_state(Menu::SaveSlots)),
+    );
+    app.add_observer(show_save_result);
+}
+
+/// What the save slot menu is showing.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+struct SaveSlotsMenu {
+    view: SaveSlotsView,
+    /// The outcome of the last action, shown below the header.
+    status: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SaveSlotsView {
+    /// A list of all slots.
+    #[default]
+    Slots,
+    /// The actions for a single slot.
+    Slot(u32),
+    ConfirmOverwrite(u32),
+    ConfirmDelete(u32),
+    /// Waiting for a slot to be loaded.
+    Loading(u32),
+}
+
+/// Marks the root of the save slot menu, so it can be rebuilt.
+#[derive(Component)]
+struct SaveSlotsRoot;
+
+fn reset_save_slots_menu(mut menu: ResMut<SaveSlotsMenu>) {
+    *menu = default();
+}
+
+fn spawn_save_slots_menu(
+    mut commands: Commands,
+    menu: Res<SaveSlotsMenu>,
+    screen: Res<State<Screen>>,
+    save_dir: Res<SaveDir>,
+    root_query: Query<Entity, With<SaveSlotsRoot>>,
+) {
+    for entity in &root_query {
+        commands.entity(entity).despawn();
+    }
+
+    let playing = screen.get() == &Screen::Gameplay;
+    let mut root = commands.spawn((
+        widget::ui_root("Save Slots Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::SaveSlots),
+        SaveSlotsRoot,
+    ));
+    root.with_children(|root| {
+        match menu.view {
+            SaveSlotsView::Slots => {
+                root.spawn(widget::header(if playing {
+                    "Save game"
+                } else {
+                    "Load game"
+                }));
+            }
+            SaveSlotsView::Slot(slot) | SaveSlotsView::Loading(slot) => {
+                root.spawn(widget::header(format!("Slot {slot}")));
+            }
+            SaveSlotsView::ConfirmOverwrite(slot) => {
+                root.spawn(widget::header(format!("Overwrite slot {slot}?")));
+            }
+            SaveSlotsView::ConfirmDelete(slot) => {
+                root.spawn(widget::header(format!("Delete slot {slot}?")));
+            }
+        }
+        if let Some(status) = &menu.status {
+            root.spawn(widget::label(status.clone()));
+        }
+
+        match menu.view {
+            SaveSlotsView::Slots => {
+                root.spawn(slot_grid()).with_children(|grid| {
+                    for slot in 1..=SLOT_COUNT {
+                        grid.spawn(widget::button(
+                            format!("Slot {slot}"),
+                            show_view(SaveSlotsView::Slot(slot)),
+                        ));
+                        grid.spawn(widget::label(slot_description(
+                            &save_dir.read_metadata(slot),
+                        )));
+                    }
+                });
+                root.spawn(widget::button("Back", go_back_on_click));
+            }
+            SaveSlotsView::Slot(slot) => {
+                let metadata = save_dir.read_metadata(slot);
+                root.spawn(widget::label(slot_description(&metadata)));
+                if playing {
+                    root.spawn(widget::button("Save", save_to_slot(slot)));
+                }
+                if metadata.is_ok() {
+                    root.spawn(widget::button("Load", load_from_slot(slot)));
+                }
+                if !matches!(metadata, Err(SaveError::EmptySlot(_))) {
+                    root.spawn(widget::button(
+                        "Delete",
+                        show_view(SaveSlotsView::ConfirmDelete(slot)),
+                    ));
+                }
+                root.spawn(widget::button("Back", go_back_on_click));
+            }
+            SaveSlotsView::ConfirmOverwrite(slot) => {
+                root.spawn(widget::label(slot_description(
+                    &save_dir.read_metadata(slot),
+                )));
+                root.spawn(widget::button("Overwrite", overwrite_slot(slot)));
+                root.spawn(widget::button("Cancel", go_back_on_click));
+            }
+            SaveSlotsView::ConfirmDelete(slot) => {
+                root.spawn(widget::label(slot_description(
+                    &save_dir.read_metadata(slot),
+                )));
+                root.spawn(widget::button("Delete", delete_slot(slot)));
+                root.spawn(widget::button("Cancel", go_back_on_click));
+            }
+            SaveSlotsView::Loading(_) => {
+                root.spawn(widget::label("Loading..."));
+            }
+        }
+    });
+}
+
+fn slot_grid() -> impl Bundle {
+    (
+        Name::new("Slot Grid"),
+        Node {
+            display: Display::Grid,
+            row_gap: Px(10.0),
+            column_gap: Px(30.0),
+            grid_template_columns: vec![GridTrack::px(380.0), GridTrack::px(600.0)],
+            align_items: AlignItems::Center,
+            ..default()
+        },
+    )
+}
+
+fn slot_description(metadata: &Result<SaveMetadata, SaveError>) -> String {
+    match metadata {
+        Ok(metadata) => format!(
+            "{}\n{} played, saved {} UTC",
+            metadata.level_name,
+            metadata.formatted_play_time(),
+            metadata.formatted_date()
+        ),
+        Err(SaveError::EmptySlot(_)) => "Empty".to_string(),
+        Err(error) => format!("Unreadable: {error}"),
+    }
+}
+
+fn show_view(view: SaveSlotsView) -> impl Fn(Trigger<Pointer<Click>>, ResMut<SaveSlotsMenu>) {
+    move |_, mut menu| {
+        *menu = SaveSlotsMenu { view, status: None };
+    }
+}
+
+/// Save right away to empty slots, but confirm before overwriting a save.
+fn save_to_slot(
+    slot: u32,
+) -> impl Fn(Trigger<Pointer<Click>>, Commands, Res<SaveDir>, ResMut<SaveSlotsMenu>) {
+    move |_, mut commands, save_dir, mut menu| {
+        if matches!(save_dir.read_metadata(slot), Err(SaveError::EmptySlot(_))) {
+            commands.trigger(SaveGame(slot));
+        } else {
+            *menu = SaveSlotsMenu {
+                view: SaveSlotsView::ConfirmOverwrite(slot),
+                status: None,
+            };
+        }
+    }
+}
+
+fn overwrite_slot(slot: u32) -> impl Fn(Trigger<Pointer<Click>>, Commands) {
+    move |_, mut commands| {
+        commands.trigger(SaveGame(slot));
+    }
+}
+
+fn load_from_slot(slot: u32) -> impl Fn(Trigger<Pointer<Click>>, Commands, ResMut<SaveSlotsMenu>) {
+    move |_, mut commands, mut menu| {
+        menu.view = SaveSlotsView::Loading(slot);
+        commands.trigger(LoadGame(slot));
+    }
+}
+
+fn delete_slot(slot: u32) -> impl Fn(Trigger<Pointer<Click>>, Res<SaveDir>, ResMut<SaveSlotsMenu>) {
+    move |_, save_dir, mut menu| {
+        let status = match save_dir.delete_slot(slot) {
+            Ok(()) => format!("Deleted slot {slot}."),
+            Err(error) => format!("Could not delete slot {slot}: {error}"),
+        };
+        *menu = SaveSlotsMenu {
+            view: SaveSlotsView::Slots,
+            status: Some(status),
+        };
+    }
+}
+
+/// Close the menu once a save has been loaded, or show what happened otherwise.
+fn show_save_result(
+    trigger: Trigger<SaveResult>,
+    current_menu: Res<State<Menu>>,
+    mut next_menu: ResMut<NextState<Menu>>,
+    mut menu: ResMut<SaveSlotsMenu>,
+) {
+    if current_menu.get() != &Menu::SaveSlots {
+        return;
+    }
+    let slot = trigger.slot;
+    let status = match (&trigger.result, menu.view) {
+        (Ok(()), SaveSlotsView::Loading(_)) => {
+            next_menu.set(Menu::None);
+            return;
+        }
+        (Ok(()), _) => format!("Saved to slot {slot}."),
+        (Err(error), SaveSlotsView::Loading(_)) => format!("Could not load slot {slot}: {error}"),
+        (Err(error), _) => format!("Could not save to slot {slot}: {error}"),
+    };
+    *menu = SaveSlotsMenu {
+        view: SaveSlotsView::Slots,
+        status: Some(status),
+    };
+}
+
+fn go_back_on_click(
+    _: Trigger<Pointer<Click>>,
+    screen: Res<State<Screen>>,
+    next_menu: ResMut<NextState<Menu>>,
+    menu: ResMut<SaveSlotsMenu>,
+) {
+    go_back(screen, next_menu, menu);
+}
+
+fn go_back(
+    screen: Res<State<Screen>>,
+    mut next_menu: ResMut<NextState<Menu>>,
+    mut menu: ResMut<SaveSlotsMenu>,
+) {
+    let view = match menu.view {
+        SaveSlotsView::Slots => {
+            next_menu.set(if screen.get() == &Screen::Title {
+                Menu::Main
+            } else {
+                Menu::Pause
+            });
+            return;
+        }
+        SaveSlotsView::Slot(_) => SaveSlotsView::Slots,
+        SaveSlotsView::ConfirmOverwrite(slot) | SaveSlotsView::ConfirmDelete(slot) => {
+            SaveSlotsView::Slot(slot)
+        }
+        // Wait for the result instead.
+        SaveSlotsView::Loading(_) => return,
+    };
+    *menu = SaveSlotsMenu { view, status: None };
+}
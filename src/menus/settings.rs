@@ -2,21 +2,21 @@
 //!
 //! Additional settings and accessibility options should go here.
 
-use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
 
-use crate::{menus::Menu, screens::Screen, theme::prelude::*};
+use crate::{input::Action, menus::Menu, screens::Screen, settings::Settings, theme::prelude::*};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Settings), spawn_settings_menu);
     app.add_systems(
         Update,
-        go_back.run_if(in_state(Menu::Settings).and(input_just_pressed(KeyCode::Escape))),
+        go_back.run_if(in_state(Menu::Settings).and(input_just_pressed(Action::Back))),
     );
 
-    app.register_type::<GlobalVolumeLabel>();
+    app.register_type::<VolumeLabel>();
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        update_volume_labels.run_if(in_state(Menu::Settings)),
     );
 }
 
@@ -44,27 +44,37 @@ fn settings_grid() -> impl Bundle {
             ..default()
         },
         children![
-            (
-                widget::label("Master Volume"),
-                Node {
-                    justify_self: JustifySelf::End,
-                    ..default()
-                }
-            ),
-            global_volume_widget(),
+            settings_label("Master Volume"),
+            volume_widget(VolumeChannel::Master),
+            settings_label("Music Volume"),
+            volume_widget(VolumeChannel::Music),
+            settings_label("Sound Effects Volume"),
+            volume_widget(VolumeChannel::SoundEffects),
+            settings_label("Interface Volume"),
+            volume_widget(VolumeChannel::Ui),
         ],
     )
 }
 
-fn global_volume_widget() -> impl Bundle {
+fn settings_label(text: &'static str) -> impl Bundle {
+    (
+        widget::label(text),
+        Node {
+            justify_self: JustifySelf::End,
+            ..default()
+        },
+    )
+}
+
+fn volume_widget(channel: VolumeChannel) -> impl Bundle {
     (
-        Name::new("Global Volume Widget"),
+        Name::new("Volume Widget"),
         Node {
             justify_self: JustifySelf::Start,
             ..default()
         },
         children![
-            widget::button_small("-", lower_global_volume),
+            widget::button_small("-", change_volume(channel, -0.1)),
             (
                 Name::new("Current Volume"),
                 Node {
@@ -72,9 +82,9 @@ fn global_volume_widget() -> impl Bundle {
                     justify_content: JustifyContent::Center,
                     ..default()
                 },
-                children![(widget::label(""), GlobalVolumeLabel)],
+                children![(widget::label(""), VolumeLabel(channel))],
             ),
-            widget::button_small("+", raise_global_volume),
+            widget::button_small("+", change_volume(channel, 0.1)),
         ],
     )
 }
@@ -82,26 +92,57 @@ fn global_volume_widget() -> impl Bundle {
 const MIN_VOLUME: f32 = 0.0;
 const MAX_VOLUME: f32 = 3.0;
 
-fn lower_global_volume(_: Trigger<Pointer<Click>>, mut global_volume: ResMut<GlobalVolume>) {
-    let linear = (global_volume.volume.to_linear() - 0.1).max(MIN_VOLUME);
-    global_volume.volume = Volume::Linear(linear);
+/// The volume settings that can be changed from the settings menu.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+enum VolumeChannel {
+    Master,
+    Music,
+    SoundEffects,
+    Ui,
+}
+
+impl VolumeChannel {
+    fn volume(self, settings: &Settings) -> f32 {
+        match self {
+            Self::Master => settings.master_volume,
+            Self::Music => settings.music_volume,
+            Self::SoundEffects => settings.sound_effect_volume,
+            Self::Ui => settings.ui_volume,
+        }
+    }
+
+    fn volume_mut(self, settings: &mut Settings) -> &mut f32 {
+        match self {
+            Self::Master => &mut settings.master_volume,
+            Self::Music => &mut settings.music_volume,
+            Self::SoundEffects => &mut settings.sound_effect_volume,
+            Self::Ui => &mut settings.ui_volume,
+        }
+    }
 }
 
-fn raise_global_volume(_: Trigger<Pointer<Click>>, mut global_volume: ResMut<GlobalVolume>) {
-    let linear = (global_volume.volume.to_linear() + 0.1).min(MAX_VOLUME);
-    global_volume.volume = Volume::Linear(linear);
+fn change_volume(
+    channel: VolumeChannel,
+    delta: f32,
+) -> impl Fn(Trigger<Pointer<Click>>, ResMut<Settings>) {
+    move |_, mut settings| {
+        let volume = channel.volume_mut(&mut settings);
+        *volume = (*volume + delta).clamp(MIN_VOLUME, MAX_VOLUME);
+    }
 }
 
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-struct GlobalVolumeLabel;
+struct VolumeLabel(VolumeChannel);
 
-fn update_global_volume_label(
-    global_volume: Res<GlobalVolume>,
-    mut label: Single<&mut Text, With<GlobalVolumeLabel>>,
+fn update_volume_labels(
+    settings: Res<Settings>,
+    mut label_query: Query<(&VolumeLabel, &mut Text)>,
 ) {
-    let percent = 100.0 * global_volume.volume.to_linear();
-    label.0 = format!("{percent:3.0}%");
+    for (label, mut text) in &mut label_query {
+        let percent = 100.0 * label.0.volume(&settings);
+        text.0 = format!("{percent:3.0}%");
+    }
 }
 
 fn go_back_on_click(
@@ -0,0 +1,407 @@
+//! Recording and replaying gameplay input.
+//!
+//! A recording starts when a level is spawned, and captures the input of the
+//! player on every fixed tick, along with the [`RngSeed`]. It ends when the
+//! level is left, and is written to a `*.replay.ron` file.
+//!
+//! Playing a replay back respawns the recorded level with the recorded seed and
+//! tick rate, then feeds the recorded input to the player instead of live
+//! input. Movement is simulated in [`FixedUpdate`], so this reproduces the
+//! recorded trajectory. The tick rate is restored once the playback ends.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use bevy_console::{AddConsoleCommand, ConsoleCommand, reply};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    PausableSystems,
+    asset_tracking::ResourceHandles,
+    demo::{
+        movement::{Dash, MovementController, MovementSystems},
+        player::Player,
+        progression::{CurrentLevel, LevelSpawned, LevelState, LoadLevel},
+    },
+    input::Action,
+    rng::{GameRng, RngSeed},
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(record_replay);
+    app.add_observer(play_replay);
+    app.add_observer(stop_replay);
+    app.add_observer(start_on_level_spawned);
+
+    app.add_systems(
+        FixedUpdate,
+        (
+            record_tick.run_if(resource_exists::<Recorder>),
+            play_tick.run_if(resource_exists::<Playback>),
+        )
+            .before(MovementSystems::Move)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(OnExit(LevelState::Playing), finish_replay);
+
+    app.add_console_command::<ReplayCommand, _>(ReplayCommand::driver);
+}
+
+/// The current version of the replay format. Increase this whenever a change
+/// makes old replays unreadable.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// The contents of a `*.replay.ron` file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub version: u32,
+    /// The [`RngSeed`] of the recorded session.
+    pub seed: u64,
+    /// The index of the recorded level in the level list.
+    pub level: usize,
+    /// The [`FixedUpdate`] rate of the recorded session.
+    pub tick_rate_hz: f64,
+    /// The input of the player on every fixed tick, starting at the first
+    /// tick after the level was spawned.
+    pub ticks: Vec<ReplayTick>,
+}
+
+/// The input of the player during a single fixed tick.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayTick {
+    /// The [`MovementController::intent`].
+    pub intent: Vec2,
+    /// The actions that affected movement, i.e. [`Action::Sprint`] while
+    /// sprinting and [`Action::Dash`] when a dash was requested.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<Action>,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Self, BevyError> {
+        let replay: Self = ron::from_str(&fs::read_to_string(path)?)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "the replay has version {}, but only version {REPLAY_VERSION} is supported",
+                replay.version
+            )
+            .into());
+        }
+        Ok(replay)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), BevyError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, ron::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Where new recordings are written to, named after the time they were made.
+pub fn default_replay_path() -> Option<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Some(
+        dirs::data_dir()?
+            .join(env!("CARGO_PKG_NAME"))
+            .join("replays")
+            .join(format!("{timestamp}.replay.ron")),
+    )
+}
+
+/// Trigger this to restart the current level and record it until it's left.
+#[derive(Event, Clone, Debug)]
+pub struct RecordReplay {
+    /// Where to write the replay, or [`default_replay_path`].
+    pub path: Option<PathBuf>,
+}
+
+/// Trigger this to play back a replay.
+#[derive(Event, Clone, Debug)]
+pub struct PlayReplay {
+    pub path: PathBuf,
+}
+
+/// Trigger this to stop recording or playing back. Recordings are written
+/// right away.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct StopReplay;
+
+#[derive(Resource, Debug)]
+struct Recorder {
+    path: PathBuf,
+    replay: Replay,
+    /// Whether the level has been spawned yet.
+    started: bool,
+}
+
+/// The replay that is being played back.
+#[derive(Resource, Debug)]
+pub struct Playback {
+    replay: Replay,
+    /// The index of the next tick to play.
+    next_tick: usize,
+    /// Whether the level has been spawned yet.
+    started: bool,
+    /// The [`FixedUpdate`] timestep from before the playback, to restore
+    /// once it ends.
+    previous_timestep: Duration,
+}
+
+/// A run condition that is true while a replay is being played back, so live
+/// input should be ignored.
+pub fn is_replaying(playback: Option<Res<Playback>>) -> bool {
+    playback.is_some()
+}
+
+/// Stop the playback, and restore the timestep from before it.
+fn end_playback(world: &mut World) {
+    if let Some(playback) = world.remove_resource::<Playback>() {
+        world
+            .resource_mut::<Time<Fixed>>()
+            .set_timestep(playback.previous_timestep);
+    }
+}
+
+/// The timestep outside of any playback.
+fn live_timestep(time: &Time<Fixed>, playback: Option<&Playback>) -> Duration {
+    playback.map_or(time.timestep(), |playback| playback.previous_timestep)
+}
+
+fn record_replay(
+    trigger: Trigger<RecordReplay>,
+    mut commands: Commands,
+    screen: Res<State<Screen>>,
+    time: Res<Time<Fixed>>,
+    playback: Option<Res<Playback>>,
+) {
+    if screen.get() != &Screen::Gameplay {
+        warn!("Replays can only be recorded during gameplay.");
+        return;
+    }
+    let Some(path) = trigger.path.clone().or_else(default_replay_path) else {
+        warn!("There is nowhere to write replays to on this platform.");
+        return;
+    };
+    let timestep = live_timestep(&time, playback.as_deref());
+    commands.queue(end_playback);
+    commands.insert_resource(Recorder {
+        path,
+        replay: Replay {
+            version: REPLAY_VERSION,
+            seed: 0,
+            level: 0,
+            tick_rate_hz: 1.0 / timestep.as_secs_f64(),
+            ticks: Vec::new(),
+        },
+        started: false,
+    });
+    // Start from a freshly spawned level, like the playback will.
+    commands.trigger(LoadLevel::Restart);
+}
+
+fn play_replay(
+    trigger: Trigger<PlayReplay>,
+    mut commands: Commands,
+    screen: Res<State<Screen>>,
+    resource_handles: Res<ResourceHandles>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut time: ResMut<Time<Fixed>>,
+    playback: Option<Res<Playback>>,
+) {
+    let replay = match Replay::read(&trigger.path) {
+        Ok(replay) => replay,
+        Err(error) => {
+            error!(
+                "Failed to read the replay {}: {error}",
+                trigger.path.display()
+            );
+            return;
+        }
+    };
+    info!(
+        "Playing back {} ticks from {}.",
+        replay.ticks.len(),
+        trigger.path.display()
+    );
+
+    commands.remove_resource::<Recorder>();
+    commands.insert_resource(RngSeed(replay.seed));
+    let previous_timestep = live_timestep(&time, playback.as_deref());
+    time.set_timestep_hz(replay.tick_rate_hz);
+    current_level.0 = replay.level;
+    commands.insert_resource(Playback {
+        replay,
+        next_tick: 0,
+        started: false,
+        previous_timestep,
+    });
+
+    // Respawn the level, which will start the playback.
+    if screen.get() == &Screen::Gameplay {
+        commands.trigger(LoadLevel::Restart);
+    } else if resource_handles.is_all_done() {
+        next_screen.set(Screen::Gameplay);
+    } else {
+        next_screen.set(Screen::Loading);
+    }
+}
+
+fn stop_replay(_: Trigger<StopReplay>, mut commands: Commands) {
+    commands.run_system_cached(finish_replay);
+    commands.remove_resource::<Recorder>();
+    commands.queue(end_playback);
+}
+
+fn start_on_level_spawned(
+    _: Trigger<LevelSpawned>,
+    recorder: Option<ResMut<Recorder>>,
+    playback: Option<ResMut<Playback>>,
+    current_level: Res<CurrentLevel>,
+    seed: Res<RngSeed>,
+    mut rng: ResMut<GameRng>,
+) {
+    if recorder.is_some() || playback.is_some() {
+        // Start the level with fresh random numbers, like the playback will.
+        *rng = GameRng::new(seed.0);
+    }
+    if let Some(mut recorder) = recorder.filter(|recorder| !recorder.started) {
+        recorder.started = true;
+        recorder.replay.seed = seed.0;
+        recorder.replay.level = current_level.0;
+        info!("Recording a replay to {}.", recorder.path.display());
+    }
+    if let Some(mut playback) = playback {
+        playback.started = true;
+    }
+}
+
+fn record_tick(
+    mut recorder: ResMut<Recorder>,
+    player_query: Query<(&MovementController, Option<&Dash>), With<Player>>,
+) {
+    if !recorder.started {
+        return;
+    }
+    let Ok((controller, dash)) = player_query.single() else {
+        return;
+    };
+
+    let mut actions = Vec::new();
+    if controller.sprint {
+        actions.push(Action::Sprint);
+    }
+    if dash.is_some_and(|dash| dash.requested) {
+        actions.push(Action::Dash);
+    }
+    recorder.replay.ticks.push(ReplayTick {
+        intent: controller.intent,
+        actions,
+    });
+}
+
+fn play_tick(
+    mut commands: Commands,
+    mut playback: ResMut<Playback>,
+    mut player_query: Query<(&mut MovementController, Option<&mut Dash>), With<Player>>,
+) {
+    if !playback.started {
+        return;
+    }
+    let Some(tick) = playback.replay.ticks.get(playback.next_tick).cloned() else {
+        info!("Finished playing back the replay.");
+        commands.queue(end_playback);
+        return;
+    };
+    playback.next_tick += 1;
+
+    for (mut controller, dash) in &mut player_query {
+        controller.intent = tick.intent;
+        controller.sprint = tick.actions.contains(&Action::Sprint);
+        if let Some(mut dash) = dash {
+            dash.requested = tick.actions.contains(&Action::Dash);
+        }
+    }
+}
+
+/// Write the recording, or stop the playback, once the level is left.
+fn finish_replay(
+    mut commands: Commands,
+    recorder: Option<Res<Recorder>>,
+    playback: Option<Res<Playback>>,
+) {
+    if let Some(recorder) = recorder.filter(|recorder| recorder.started) {
+        match recorder.replay.write(&recorder.path) {
+            Ok(()) => info!(
+                "Recorded {} ticks to {}.",
+                recorder.replay.ticks.len(),
+                recorder.path.display()
+            ),
+            Err(error) => error!(
+                "Failed to write the replay {}: {error}",
+                recorder.path.display()
+            ),
+        }
+        commands.remove_resource::<Recorder>();
+    }
+    if playback.is_some_and(|playback| playback.started) {
+        info!("Stopped playing back the replay.");
+        commands.queue(end_playback);
+    }
+}
+
+/// Records or plays back gameplay input
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "replay")]
+struct ReplayCommand {
+    #[command(subcommand)]
+    action: ReplayAction,
+}
+
+#[derive(Subcommand)]
+enum ReplayAction {
+    /// Restarts the level and records it until it is left
+    Record {
+        /// Where to write the replay
+        path: Option<PathBuf>,
+    },
+    /// Restarts the recorded level and plays the replay back
+    Play {
+        /// The replay to play
+        path: PathBuf,
+    },
+    /// Stops recording or playing back
+    Stop,
+}
+
+impl ReplayCommand {
+    fn driver(mut log: ConsoleCommand<ReplayCommand>, mut commands: Commands) {
+        if let Some(Ok(ReplayCommand { action })) = log.take() {
+            match action {
+                ReplayAction::Record { path } => {
+                    reply!(log, "Recording a replay");
+                    commands.trigger(RecordReplay { path });
+                }
+                ReplayAction::Play { path } => {
+                    reply!(log, "Playing back {}", path.display());
+                    commands.trigger(PlayReplay { path });
+                }
+                ReplayAction::Stop => {
+                    reply!(log, "Stopping the replay");
+                    commands.trigger(StopReplay);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Seeded randomness.
+//!
+//! All randomness should come from [`GameRng`] instead of [`rand::thread_rng`],
+//! so it can be reproduced by using the same [`RngSeed`]. Each purpose has its
+//! own [`RngStream`], so e.g. playing more sounds doesn't change the outcome of
+//! gameplay.
+
+use bevy::prelude::*;
+use bevy_console::{AddConsoleCommand, ConsoleCommand, reply};
+use clap::Parser;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::AppSystems;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<RngSeed>();
+    app.register_type::<RngStream>();
+    // The seed may have been set on the command line already.
+    let seed = *app
+        .world_mut()
+        .get_resource_or_insert_with(|| RngSeed(rand::random()));
+    app.insert_resource(GameRng::new(seed.0));
+
+    app.add_systems(
+        Update,
+        reseed
+            .run_if(resource_changed::<RngSeed>)
+            .before(AppSystems::TickTimers),
+    );
+
+    app.add_console_command::<SeedCommand, _>(SeedCommand::driver);
+}
+
+/// The seed that all randomness in the game is derived from. Replays store it,
+/// so they play out the same way as the recorded session.
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct RngSeed(pub u64);
+
+/// What random numbers are used for. Each stream is seeded independently.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    Audio,
+    Gameplay,
+    Ai,
+    /// Effects that don't affect gameplay, such as particles.
+    Cosmetic,
+}
+
+impl RngStream {
+    const ALL: [Self; 4] = [Self::Audio, Self::Gameplay, Self::Ai, Self::Cosmetic];
+}
+
+/// The random number generators of all [`RngStream`]s.
+#[derive(Resource, Clone, Debug)]
+pub struct GameRng {
+    streams: [ChaCha8Rng; RngStream::ALL.len()],
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            streams: RngStream::ALL.map(|stream| {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                rng.set_stream(stream as u64);
+                rng
+            }),
+        }
+    }
+
+    /// The random number generator for a purpose.
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        &mut self.streams[stream as usize]
+    }
+}
+
+/// Start all streams over whenever the seed changes.
+fn reseed(seed: Res<RngSeed>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(seed.0);
+}
+
+/// Prints or changes the random seed
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "seed")]
+struct SeedCommand {
+    /// The new seed
+    seed: Option<u64>,
+}
+
+impl SeedCommand {
+    fn driver(mut log: ConsoleCommand<SeedCommand>, mut rng_seed: ResMut<RngSeed>) {
+        if let Some(Ok(SeedCommand { seed })) = log.take() {
+            match seed {
+                Some(seed) => {
+                    rng_seed.0 = seed;
+                    reply!(log, "Seed set to {seed}");
+                }
+                None => reply!(log, "Seed: {}", rng_seed.0),
+            }
+        }
+    }
+}
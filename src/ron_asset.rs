@@ -0,0 +1,56 @@
+//! Loading [`Asset`]s from RON files.
+
+use std::{error::Error, marker::PhantomData};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+/// An [`Asset`] that can be deserialized from a RON file.
+pub trait RonAsset: Asset + DeserializeOwned {
+    /// The file extensions handled by this asset's loader, without the leading dot.
+    /// Use a compound extension such as `"level.ron"` to tell different RON assets apart.
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Load the assets that the file refers to by path.
+    fn load_dependencies(&mut self, _load_context: &mut LoadContext) {}
+}
+
+pub trait InitRonAsset {
+    /// Register `T` as an [`Asset`], along with a loader that reads it from RON files.
+    fn init_ron_asset<T: RonAsset>(&mut self) -> &mut Self;
+}
+
+impl InitRonAsset for App {
+    fn init_ron_asset<T: RonAsset>(&mut self) -> &mut Self {
+        self.init_asset::<T>()
+            .register_asset_loader(RonAssetLoader::<T>(PhantomData))
+    }
+}
+
+struct RonAssetLoader<T>(PhantomData<fn() -> T>);
+
+impl<T: RonAsset> AssetLoader for RonAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut asset = ron::de::from_bytes::<T>(&bytes)?;
+        asset.load_dependencies(load_context);
+        Ok(asset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        T::EXTENSIONS
+    }
+}
@@ -0,0 +1,846 @@
+//! Saving and loading the game.
+//!
+//! A save captures every entity marked with a [`SaveId`], along with some
+//! resources. Only types registered with [`RegisterSaveable`] are saved, and
+//! they are serialized through the type registry, so new gameplay components
+//! only need to derive [`Reflect`] to be saveable.
+//!
+//! Loading respawns the saved level, then applies the saved components to the
+//! entities with the same [`SaveId`]. Entities of the level that are missing
+//! from the save are despawned, so collected items and the like stay gone.
+//!
+//! Saves live in numbered slots in the [`SaveDir`], which is in the user data
+//! directory unless it's inserted before adding the plugin. Each file starts
+//! with a format version and [`SaveMetadata`], so slots can be listed without
+//! reading the whole file.
+
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    error::Error,
+    fmt, fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    ecs::relationship::RelationshipHookMode,
+    prelude::*,
+    reflect::TypeRegistry,
+    scene::{
+        DynamicEntity, SceneFilter,
+        serde::{SceneDeserializer, SceneSerializer},
+    },
+};
+use bevy_console::{AddConsoleCommand, ConsoleCommand, reply};
+use clap::Parser;
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{DeserializeSeed, IgnoredAny, MapAccess, Visitor},
+};
+
+use crate::{
+    asset_tracking::ResourceHandles,
+    demo::{
+        level::Level,
+        progression::{LevelSpawned, LoadLevel, PlayTime},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SaveDir>();
+    app.register_type::<SaveId>();
+    app.save_component::<SaveId>();
+    app.save_component::<Transform>();
+
+    app.add_observer(save_game);
+    app.add_observer(load_game);
+    app.add_observer(apply_pending_load);
+
+    app.add_console_command::<SaveCommand, _>(SaveCommand::driver);
+    app.add_console_command::<LoadCommand, _>(LoadCommand::driver);
+    app.add_console_command::<SavesCommand, _>(SavesCommand::driver);
+    app.add_console_command::<DeleteSaveCommand, _>(DeleteSaveCommand::driver);
+}
+
+/// The current version of the save format. Increase this whenever a change
+/// makes old saves unreadable.
+pub const SAVE_VERSION: u32 = 1;
+
+/// The number of save slots.
+pub const SLOT_COUNT: u32 = 3;
+
+/// Marks an entity to be saved. The id must identify the entity within its
+/// level, and stay the same when the level is spawned again.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub struct SaveId(pub u32);
+
+/// The components and resources that are included in saves.
+#[derive(Resource)]
+pub struct SaveRegistry {
+    components: SceneFilter,
+    resources: SceneFilter,
+}
+
+impl Default for SaveRegistry {
+    fn default() -> Self {
+        Self {
+            components: SceneFilter::deny_all(),
+            resources: SceneFilter::deny_all(),
+        }
+    }
+}
+
+pub trait RegisterSaveable {
+    /// Save this component on entities with a [`SaveId`].
+    fn save_component<T: Component + Reflect + TypePath>(&mut self) -> &mut Self;
+
+    /// Save this resource.
+    fn save_resource<T: Resource + Reflect + TypePath>(&mut self) -> &mut Self;
+}
+
+impl RegisterSaveable for App {
+    fn save_component<T: Component + Reflect + TypePath>(&mut self) -> &mut Self {
+        let mut registry = self.world_mut().get_resource_or_init::<SaveRegistry>();
+        registry.components =
+            std::mem::take(&mut registry.components).allow_by_id(TypeId::of::<T>());
+        self
+    }
+
+    fn save_resource<T: Resource + Reflect + TypePath>(&mut self) -> &mut Self {
+        let mut registry = self.world_mut().get_resource_or_init::<SaveRegistry>();
+        registry.resources = std::mem::take(&mut registry.resources).allow_by_id(TypeId::of::<T>());
+        self
+    }
+}
+
+/// Shown when choosing a slot, without loading the whole save.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveMetadata {
+    /// When the game was saved, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The name of the level that was being played.
+    pub level_name: String,
+    /// The total time spent playing, in seconds.
+    pub play_time: f32,
+}
+
+impl SaveMetadata {
+    /// The play time as `h:mm:ss`, or `m:ss` for less than an hour.
+    pub fn formatted_play_time(&self) -> String {
+        let seconds = self.play_time as u64;
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if hours > 0 {
+            format!("{hours}:{minutes:02}:{seconds:02}")
+        } else {
+            format!("{minutes}:{seconds:02}")
+        }
+    }
+
+    /// The time of the save as `YYYY-MM-DD hh:mm` in UTC.
+    pub fn formatted_date(&self) -> String {
+        let days = (self.timestamp / 86400) as i64;
+        let minutes = self.timestamp % 86400 / 60;
+        // Convert days since the epoch to a civil date, see
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        format!(
+            "{year}-{month:02}-{day:02} {:02}:{:02}",
+            minutes / 60,
+            minutes % 60
+        )
+    }
+}
+
+/// Everything that can go wrong while saving or loading.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The platform has no user data directory, e.g. on the web.
+    Unsupported,
+    /// The slot is empty.
+    EmptySlot(u32),
+    /// There is no slot with this number.
+    InvalidSlot(u32),
+    /// Saving is only possible during gameplay.
+    NotPlaying,
+    Io(io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    /// The save was made with an incompatible version of the game.
+    UnsupportedVersion {
+        found: u32,
+    },
+    /// The save contains a type that doesn't exist (anymore).
+    UnknownType {
+        type_path: String,
+    },
+    /// The save contains a type that isn't registered with [`RegisterSaveable`].
+    UnsaveableType {
+        type_path: String,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "saving is not supported on this platform"),
+            Self::EmptySlot(slot) => write!(f, "slot {slot} is empty"),
+            Self::InvalidSlot(slot) => {
+                write!(f, "there is no slot {slot}, only slots 1 to {SLOT_COUNT}")
+            }
+            Self::NotPlaying => write!(f, "the game can only be saved during gameplay"),
+            Self::Io(error) => write!(f, "could not access the save file: {error}"),
+            Self::Serialize(error) => write!(f, "could not serialize the game: {error}"),
+            Self::Deserialize(error) => write!(f, "the save file is corrupted: {error}"),
+            Self::UnsupportedVersion { found } => write!(
+                f,
+                "the save file has version {found}, but only version {SAVE_VERSION} is supported"
+            ),
+            Self::UnknownType { type_path } => write!(
+                f,
+                "the save file contains the unknown type `{type_path}`, \
+                 it may have been renamed or removed since the game was saved"
+            ),
+            Self::UnsaveableType { type_path } => write!(
+                f,
+                "the save file contains the type `{type_path}`, which is not saveable"
+            ),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Serialize(error) => Some(error),
+            Self::Deserialize(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// The directory that save slots are stored in, if any.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct SaveDir(pub Option<PathBuf>);
+
+impl Default for SaveDir {
+    /// The `saves` directory in the user data directory, if the platform has one.
+    fn default() -> Self {
+        Self(dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("saves")))
+    }
+}
+
+impl SaveDir {
+    /// The location of a save slot.
+    pub fn slot_path(&self, slot: u32) -> Result<PathBuf, SaveError> {
+        if !(1..=SLOT_COUNT).contains(&slot) {
+            return Err(SaveError::InvalidSlot(slot));
+        }
+        let dir = self.0.as_ref().ok_or(SaveError::Unsupported)?;
+        Ok(dir.join(format!("slot{slot}.save.ron")))
+    }
+
+    fn read_slot(&self, slot: u32) -> Result<String, SaveError> {
+        fs::read_to_string(self.slot_path(slot)?).map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => SaveError::EmptySlot(slot),
+            _ => SaveError::Io(error),
+        })
+    }
+
+    /// Read the metadata of a save slot.
+    pub fn read_metadata(&self, slot: u32) -> Result<SaveMetadata, SaveError> {
+        let text = self.read_slot(slot)?;
+        Ok(parse_outline(&text)?.metadata)
+    }
+
+    /// The slot that was saved to most recently, if any.
+    pub fn latest_slot(&self) -> Option<u32> {
+        (1..=SLOT_COUNT)
+            .filter_map(|slot| Some((self.read_metadata(slot).ok()?.timestamp, slot)))
+            .max()
+            .map(|(_, slot)| slot)
+    }
+
+    /// Delete the save in a slot.
+    pub fn delete_slot(&self, slot: u32) -> Result<(), SaveError> {
+        fs::remove_file(self.slot_path(slot)?).map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => SaveError::EmptySlot(slot),
+            _ => SaveError::Io(error),
+        })
+    }
+}
+
+/// Trigger this to save the current game to a slot.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SaveGame(pub u32);
+
+/// Trigger this to load the game from a slot.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LoadGame(pub u32);
+
+/// Triggered after [`SaveGame`] or [`LoadGame`] to report the result.
+#[derive(Event, Debug)]
+pub struct SaveResult {
+    pub slot: u32,
+    pub result: Result<(), SaveError>,
+}
+
+fn save_game(trigger: Trigger<SaveGame>, mut commands: Commands) {
+    let slot = trigger.0;
+    commands.queue(move |world: &mut World| {
+        let result = write_save(world, slot);
+        match &result {
+            Ok(()) => info!("Saved the game to slot {slot}."),
+            Err(error) => error!("Failed to save the game to slot {slot}: {error}"),
+        }
+        world.trigger(SaveResult { slot, result });
+    });
+}
+
+fn write_save(world: &mut World, slot: u32) -> Result<(), SaveError> {
+    if world.resource::<State<Screen>>().get() != &Screen::Gameplay {
+        return Err(SaveError::NotPlaying);
+    }
+
+    let metadata = SaveMetadata {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        level_name: world
+            .query::<&Level>()
+            .iter(world)
+            .next()
+            .map(|level| level.name.clone())
+            .unwrap_or_default(),
+        play_time: world.resource::<PlayTime>().0.as_secs_f32(),
+    };
+    let text = serialize_save(world, metadata)?;
+
+    let path = world.resource::<SaveDir>().slot_path(slot)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write to a temporary file first, so a crash can't corrupt an existing save.
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, text)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// Serialize the entities with a [`SaveId`] and the saveable resources.
+fn serialize_save(world: &mut World, metadata: SaveMetadata) -> Result<String, SaveError> {
+    let entities = world
+        .query_filtered::<Entity, With<SaveId>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    let save_registry = world.resource::<SaveRegistry>();
+    let scene = DynamicSceneBuilder::from_world(world)
+        .with_component_filter(save_registry.components.clone())
+        .with_resource_filter(save_registry.resources.clone())
+        .extract_entities(entities.into_iter())
+        .extract_resources()
+        .build();
+
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        metadata,
+        scene: SceneSerializer::new(&scene, &type_registry),
+    };
+    ron::ser::to_string_pretty(&file, default()).map_err(SaveError::Serialize)
+}
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    metadata: SaveMetadata,
+    scene: SceneSerializer<'a>,
+}
+
+/// The saved scene, waiting for its level to be spawned.
+#[derive(Resource)]
+struct PendingLoad(DynamicScene);
+
+fn load_game(trigger: Trigger<LoadGame>, mut commands: Commands) {
+    let slot = trigger.0;
+    commands.queue(move |world: &mut World| {
+        let result = read_save(world, slot);
+        match &result {
+            Ok(()) => info!("Loading the game from slot {slot}."),
+            Err(error) => error!("Failed to load the game from slot {slot}: {error}"),
+        }
+        world.trigger(SaveResult { slot, result });
+    });
+}
+
+fn read_save(world: &mut World, slot: u32) -> Result<(), SaveError> {
+    let text = world.resource::<SaveDir>().read_slot(slot)?;
+    let scene = deserialize_save(world, &text)?;
+    world.insert_resource(PendingLoad(scene));
+
+    // Respawn the level, which will pick up the pending load.
+    if world.resource::<State<Screen>>().get() == &Screen::Gameplay {
+        world.trigger(LoadLevel::Restart);
+    } else if world.resource::<ResourceHandles>().is_all_done() {
+        world
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Gameplay);
+    } else {
+        world
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Loading);
+    }
+    Ok(())
+}
+
+/// Deserialize a save and restore its resources. The entities are returned,
+/// to be applied with [`apply_save`] once the level has been spawned.
+fn deserialize_save(world: &mut World, text: &str) -> Result<DynamicScene, SaveError> {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+    let save_registry = world.resource::<SaveRegistry>();
+
+    // Check that every type is known and saveable before deserializing, for a
+    // clearer error and so a save can't insert arbitrary components.
+    let outline = parse_outline(text)?;
+    let resources = outline
+        .scene
+        .resources
+        .keys()
+        .map(|type_path| (type_path, &save_registry.resources));
+    let components = outline
+        .scene
+        .entities
+        .values()
+        .flat_map(|entity| entity.components.keys())
+        .map(|type_path| (type_path, &save_registry.components));
+    for (type_path, filter) in resources.chain(components) {
+        let Some(registration) = type_registry.get_with_type_path(type_path) else {
+            return Err(SaveError::UnknownType {
+                type_path: type_path.clone(),
+            });
+        };
+        if !filter.is_allowed_by_id(registration.type_id()) {
+            return Err(SaveError::UnsaveableType {
+                type_path: type_path.clone(),
+            });
+        }
+    }
+
+    let mut deserializer = ron::de::Deserializer::from_str(text).map_err(SaveError::Deserialize)?;
+    let scene = SaveFileSeed(&type_registry)
+        .deserialize(&mut deserializer)
+        .map_err(|error| SaveError::Deserialize(deserializer.span_error(error)))?;
+
+    // Restore resources right away, so the right level is spawned.
+    for resource in &scene.resources {
+        let Some(reflect_resource) = resource
+            .get_represented_type_info()
+            .and_then(|info| type_registry.get_type_data::<ReflectResource>(info.type_id()))
+        else {
+            continue;
+        };
+        reflect_resource.apply_or_insert(world, resource.as_partial_reflect(), &type_registry);
+    }
+    Ok(scene)
+}
+
+/// Apply the saved components once the level has been spawned.
+fn apply_pending_load(_: Trigger<LevelSpawned>, mut commands: Commands) {
+    commands.queue(|world: &mut World| {
+        if let Some(PendingLoad(scene)) = world.remove_resource::<PendingLoad>() {
+            apply_save(world, &scene);
+        }
+    });
+}
+
+/// Apply the saved components to the entities with the same [`SaveId`], and
+/// despawn the ones that weren't saved.
+fn apply_save(world: &mut World, scene: &DynamicScene) {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+
+    let mut spawned = world
+        .query::<(Entity, &SaveId)>()
+        .iter(world)
+        .map(|(entity, id)| (*id, entity))
+        .collect::<HashMap<_, _>>();
+    for DynamicEntity { components, .. } in &scene.entities {
+        let Some(id) = components
+            .iter()
+            .find_map(|component| SaveId::from_reflect(component.as_partial_reflect()))
+        else {
+            continue;
+        };
+        let Some(entity) = spawned.remove(&id) else {
+            warn!("The saved entity {id:?} does not exist in the level anymore.");
+            continue;
+        };
+        let mut entity = world.entity_mut(entity);
+        for component in components {
+            let Some(reflect_component) = component
+                .get_represented_type_info()
+                .and_then(|info| type_registry.get_type_data::<ReflectComponent>(info.type_id()))
+            else {
+                continue;
+            };
+            // Saved entities are matched by `SaveId`, so entity references
+            // are kept as they are.
+            reflect_component.apply_or_insert_mapped(
+                &mut entity,
+                component.as_partial_reflect(),
+                &type_registry,
+                &mut (),
+                RelationshipHookMode::Run,
+            );
+        }
+    }
+
+    // Whatever wasn't saved was gone when the game was saved.
+    for entity in spawned.into_values() {
+        world.despawn(entity);
+    }
+}
+
+/// The parts of a save file that can be read without the type registry.
+#[derive(Deserialize)]
+struct SaveOutline {
+    metadata: SaveMetadata,
+    scene: SceneOutline,
+}
+
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct SceneOutline {
+    #[serde(default)]
+    resources: HashMap<String, IgnoredAny>,
+    #[serde(default)]
+    entities: HashMap<u64, EntityOutline>,
+}
+
+#[derive(Deserialize)]
+struct EntityOutline {
+    #[serde(default)]
+    components: HashMap<String, IgnoredAny>,
+}
+
+/// Check the version of a save file, then read its outline.
+fn parse_outline(text: &str) -> Result<SaveOutline, SaveError> {
+    let SaveVersion { version } = ron::from_str(text).map_err(SaveError::Deserialize)?;
+    if version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion { found: version });
+    }
+    ron::from_str(text).map_err(SaveError::Deserialize)
+}
+
+/// Deserializes the scene of a save file.
+#[derive(Clone, Copy)]
+struct SaveFileSeed<'a>(&'a TypeRegistry);
+
+impl<'de> DeserializeSeed<'de> for SaveFileSeed<'_> {
+    type Value = DynamicScene;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("SaveFile", &["version", "metadata", "scene"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for SaveFileSeed<'_> {
+    type Value = DynamicScene;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a save file")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut scene = None;
+        while let Some(field) = map.next_key::<SaveFileField>()? {
+            match field {
+                SaveFileField::Scene => {
+                    scene = Some(map.next_value_seed(SceneDeserializer {
+                        type_registry: self.0,
+                    })?);
+                }
+                SaveFileField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        scene.ok_or_else(|| serde::de::Error::missing_field("scene"))
+    }
+}
+
+/// The fields of a save file. Only the scene needs a seed, the rest has
+/// already been checked by [`parse_outline`].
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum SaveFileField {
+    Scene,
+    #[serde(other)]
+    Other,
+}
+
+/// Saves the game to a slot
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "save")]
+struct SaveCommand {
+    /// The slot to save to
+    slot: u32,
+}
+
+impl SaveCommand {
+    fn driver(
+        mut log: ConsoleCommand<SaveCommand>,
+        mut commands: Commands,
+        save_dir: Res<SaveDir>,
+    ) {
+        if let Some(Ok(SaveCommand { slot })) = log.take() {
+            if let Err(error) = save_dir.slot_path(slot) {
+                reply!(log, "Failed to save to slot {slot}: {error}");
+                return;
+            }
+            reply!(log, "Saving to slot {slot}");
+            commands.trigger(SaveGame(slot));
+        }
+    }
+}
+
+/// Loads the game from a slot
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "load")]
+struct LoadCommand {
+    /// The slot to load from
+    slot: u32,
+}
+
+impl LoadCommand {
+    fn driver(
+        mut log: ConsoleCommand<LoadCommand>,
+        mut commands: Commands,
+        save_dir: Res<SaveDir>,
+    ) {
+        if let Some(Ok(LoadCommand { slot })) = log.take() {
+            if let Err(error) = save_dir.slot_path(slot) {
+                reply!(log, "Failed to load slot {slot}: {error}");
+                return;
+            }
+            reply!(log, "Loading from slot {slot}");
+            commands.trigger(LoadGame(slot));
+        }
+    }
+}
+
+/// Lists the save slots
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "saves")]
+struct SavesCommand;
+
+impl SavesCommand {
+    fn driver(mut log: ConsoleCommand<SavesCommand>, save_dir: Res<SaveDir>) {
+        if let Some(Ok(SavesCommand)) = log.take() {
+            for slot in 1..=SLOT_COUNT {
+                match save_dir.read_metadata(slot) {
+                    Ok(metadata) => reply!(
+                        log,
+                        "{slot}: {} ({} played, saved {} UTC)",
+                        metadata.level_name,
+                        metadata.formatted_play_time(),
+                        metadata.formatted_date()
+                    ),
+                    Err(error) => reply!(log, "{slot}: {error}"),
+                }
+            }
+        }
+    }
+}
+
+/// Deletes the save in a slot
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "delete_save")]
+struct DeleteSaveCommand {
+    /// The slot to delete
+    slot: u32,
+}
+
+impl DeleteSaveCommand {
+    fn driver(mut log: ConsoleCommand<DeleteSaveCommand>, save_dir: Res<SaveDir>) {
+        if let Some(Ok(DeleteSaveCommand { slot })) = log.take() {
+            match save_dir.delete_slot(slot) {
+                Ok(()) => reply!(log, "Deleted slot {slot}"),
+                Err(error) => reply!(log, "Failed to delete slot {slot}: {error}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Health(u32);
+
+    #[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Secret(u32);
+
+    fn app(saveable_health: bool) -> App {
+        let mut app = App::new();
+        app.register_type::<SaveId>();
+        app.register_type::<Health>();
+        app.register_type::<Secret>();
+        app.save_component::<SaveId>();
+        if saveable_health {
+            app.save_component::<Health>();
+        }
+        app
+    }
+
+    fn metadata() -> SaveMetadata {
+        SaveMetadata {
+            timestamp: 0,
+            level_name: "Test".to_string(),
+            play_time: 12.5,
+        }
+    }
+
+    /// The [`Health`] of every entity with a [`SaveId`], sorted by the id.
+    fn saved_health(world: &mut World) -> Vec<(u32, u32)> {
+        let mut health = world
+            .query::<(&SaveId, &Health)>()
+            .iter(world)
+            .map(|(id, health)| (id.0, health.0))
+            .collect::<Vec<_>>();
+        health.sort();
+        health
+    }
+
+    /// Save two entities and one that isn't saveable.
+    fn save(world: &mut World) -> String {
+        world.spawn((SaveId(1), Health(10)));
+        world.spawn((SaveId(2), Health(20), Secret(7)));
+        world.spawn(Health(30));
+        serialize_save(world, metadata()).unwrap()
+    }
+
+    #[test]
+    fn loading_applies_saved_components_by_save_id() {
+        let mut app = app(true);
+        let world = app.world_mut();
+        let text = save(world);
+        assert_eq!(parse_outline(&text).unwrap().metadata, metadata());
+
+        // Respawn the level, with new entities in a different order.
+        world.clear_entities();
+        world.spawn((SaveId(3), Health(100)));
+        world.spawn((SaveId(2), Health(100)));
+        world.spawn((SaveId(1), Health(100)));
+        let scene = deserialize_save(world, &text).unwrap();
+        apply_save(world, &scene);
+
+        assert_eq!(saved_health(world), vec![(1, 10), (2, 20)]);
+        assert_eq!(world.query::<&Secret>().iter(world).count(), 0);
+    }
+
+    #[test]
+    fn loading_rejects_other_versions() {
+        let mut app = app(true);
+        let text =
+            save(app.world_mut()).replacen(&format!("version: {SAVE_VERSION}"), "version: 999", 1);
+        assert!(matches!(
+            deserialize_save(app.world_mut(), &text),
+            Err(SaveError::UnsupportedVersion { found: 999 })
+        ));
+    }
+
+    #[test]
+    fn loading_rejects_unsaveable_types() {
+        let text = save(app(true).world_mut());
+        let mut app = app(false);
+        let error = deserialize_save(app.world_mut(), &text).err();
+        assert!(
+            matches!(
+                &error,
+                Some(SaveError::UnsaveableType { type_path }) if type_path == Health::type_path()
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn slots_are_in_the_save_dir() {
+        let save_dir = SaveDir(Some(PathBuf::from("saves")));
+        assert_eq!(
+            save_dir.slot_path(SLOT_COUNT).unwrap(),
+            PathBuf::from("saves").join(format!("slot{SLOT_COUNT}.save.ron"))
+        );
+        assert!(matches!(
+            SaveDir(None).slot_path(1),
+            Err(SaveError::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn slots_outside_the_range_are_rejected() {
+        let save_dir = SaveDir(Some(PathBuf::from("saves")));
+        for slot in [0, SLOT_COUNT + 1, 99] {
+            assert!(matches!(
+                save_dir.slot_path(slot),
+                Err(SaveError::InvalidSlot(invalid)) if invalid == slot
+            ));
+        }
+    }
+
+    fn formatted_date(timestamp: u64) -> String {
+        SaveMetadata {
+            timestamp,
+            ..metadata()
+        }
+        .formatted_date()
+    }
+
+    #[test]
+    fn formats_the_epoch() {
+        assert_eq!(formatted_date(0), "1970-01-01 00:00");
+    }
+
+    #[test]
+    fn formats_leap_days() {
+        assert_eq!(formatted_date(1_709_210_040), "2024-02-29 12:34");
+        assert_eq!(formatted_date(1_709_251_200), "2024-03-01 00:00");
+    }
+
+    #[test]
+    fn formats_the_end_of_the_year() {
+        assert_eq!(formatted_date(946_684_740), "1999-12-31 23:59");
+        assert_eq!(formatted_date(946_684_800), "2000-01-01 00:00");
+    }
+}
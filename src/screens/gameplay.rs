@@ -2,16 +2,9 @@
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
 
-use crate::{
-    Pause,
-    demo::{level::Level, store::Store},
-    menus::Menu,
-    screens::Screen,
-};
+use crate::{Pause, input::Action, menus::Menu, screens::Screen};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_level);
-
     // Toggle pause on key press.
     app.add_systems(
         Update,
@@ -19,12 +12,12 @@ pub(super) fn plugin(app: &mut App) {
             (pause, spawn_pause_overlay, open_pause_menu).run_if(
                 in_state(Screen::Gameplay)
                     .and(in_state(Menu::None))
-                    .and(input_just_pressed(KeyCode::KeyP).or(input_just_pressed(KeyCode::Escape))),
+                    .and(input_just_pressed(Action::Pause).or(input_just_pressed(Action::Back))),
             ),
             close_menu.run_if(
                 in_state(Screen::Gameplay)
                     .and(not(in_state(Menu::None)))
-                    .and(input_just_pressed(KeyCode::KeyP)),
+                    .and(input_just_pressed(Action::Pause)),
             ),
         ),
     );
@@ -35,10 +28,6 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-fn spawn_level(mut c: Commands, mut store: Store) {
-    c.spawn(Level::level(&mut store));
-}
-
 fn unpause(mut next_pause: ResMut<NextState<Pause>>) {
     next_pause.set(Pause(false));
 }
@@ -3,15 +3,11 @@
 
 use bevy::prelude::*;
 
-use crate::{asset_tracking::ResourceHandles, screens::Screen, theme::prelude::*};
+use crate::{screens::Screen, theme::prelude::*};
 
 pub(super) fn plugin(app: &mut App) {
+    // Gameplay is entered once all assets are loaded, see `screens::state_plugin`.
     app.add_systems(OnEnter(Screen::Loading), spawn_loading_screen);
-
-    app.add_systems(
-        Update,
-        enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
-    );
 }
 
 fn spawn_loading_screen(mut commands: Commands) {
@@ -21,11 +17,3 @@ fn spawn_loading_screen(mut commands: Commands) {
         children![widget::label("Loading...")],
     ));
 }
-
-fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Gameplay);
-}
-
-fn all_assets_loaded(resource_handles: Res<ResourceHandles>) -> bool {
-    resource_handles.is_all_done()
-}
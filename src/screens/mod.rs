@@ -7,9 +7,9 @@ mod title;
 
 use bevy::prelude::*;
 
-pub(super) fn plugin(app: &mut App) {
-    app.init_state::<Screen>();
+use crate::{asset_tracking::ResourceHandles, cli::Cli};
 
+pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         gameplay::plugin,
         loading::plugin,
@@ -18,6 +18,22 @@ pub(super) fn plugin(app: &mut App) {
     ));
 }
 
+/// The [`Screen`] state, and entering gameplay once loading is done, without
+/// any of the screens' UI.
+pub(super) fn state_plugin(app: &mut App) {
+    // Start at the screen that was requested on the command line, if any.
+    let initial_screen = app
+        .world_mut()
+        .get_resource_or_init::<Cli>()
+        .initial_screen();
+    app.insert_state(initial_screen);
+
+    app.add_systems(
+        Update,
+        enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
+    );
+}
+
 /// The game's main screen states.
 #[derive(States, Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 #[states(scoped_entities)]
@@ -28,3 +44,11 @@ pub enum Screen {
     Loading,
     Gameplay,
 }
+
+fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+fn all_assets_loaded(resource_handles: Res<ResourceHandles>) -> bool {
+    resource_handles.is_all_done()
+}
@@ -0,0 +1,149 @@
+//! Persistent user settings.
+//!
+//! [`Settings`] is read from the user config directory while the app is being
+//! built, and written back whenever it changes. Every user-facing option should
+//! be stored here so players don't have to re-tune it on each launch.
+//!
+//! Insert a [`SettingsPath`] before adding the plugin to read and write the
+//! settings somewhere else, or nowhere at all.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{MusicVolume, Muted, SoundEffectVolume, UiVolume};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Settings>();
+    let path = app
+        .world_mut()
+        .get_resource_or_insert_with(SettingsPath::default)
+        .0
+        .clone();
+    app.insert_resource(Settings::load(path.as_deref()));
+
+    app.add_systems(
+        Update,
+        (
+            apply_audio_settings.run_if(resource_changed::<Settings>.or(resource_changed::<Muted>)),
+            save_settings.run_if(resource_changed::<Settings>.and(not(resource_added::<Settings>))),
+        ),
+    );
+}
+
+/// Where the [`Settings`] are stored, if anywhere.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct SettingsPath(pub Option<PathBuf>);
+
+impl Default for SettingsPath {
+    /// The settings file in the user config directory, if the platform has one.
+    fn default() -> Self {
+        Self(
+            dirs::config_dir()
+                .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(Settings::FILE_NAME)),
+        )
+    }
+}
+
+/// All user settings that survive a restart.
+///
+/// Missing fields fall back to their defaults, so settings added later don't
+/// invalidate existing files.
+#[derive(Resource, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Linear volume multiplier applied to all audio.
+    pub master_volume: f32,
+    /// Linear volume multiplier applied to music, on top of the master volume.
+    pub music_volume: f32,
+    /// Linear volume multiplier applied to sound effects, on top of the master volume.
+    pub sound_effect_volume: f32,
+    /// Linear volume multiplier applied to interface sounds, on top of the master volume.
+    pub ui_volume: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sound_effect_volume: 1.0,
+            ui_volume: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    const FILE_NAME: &'static str = "settings.ron";
+
+    /// Load the settings from `path`.
+    /// Falls back to the defaults if there is no path, or the file is missing
+    /// or can't be parsed.
+    pub fn load(path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            info!("No settings path available, settings will not be persisted.");
+            return Self::default();
+        };
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(error) => {
+                warn!(
+                    "Failed to read {}: {error}. Using default settings.",
+                    path.display()
+                );
+                return Self::default();
+            }
+        };
+
+        ron::from_str(&text).unwrap_or_else(|error| {
+            warn!(
+                "Failed to parse {}: {error}. Using default settings.",
+                path.display()
+            );
+            Self::default()
+        })
+    }
+
+    /// Write the settings to `path`, creating its directory if needed.
+    pub fn save(&self, path: Option<&Path>) -> io::Result<()> {
+        let Some(path) = path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, default()).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+}
+
+fn apply_audio_settings(
+    settings: Res<Settings>,
+    muted: Res<Muted>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut music_volume: ResMut<MusicVolume>,
+    mut sound_effect_volume: ResMut<SoundEffectVolume>,
+    mut ui_volume: ResMut<UiVolume>,
+) {
+    global_volume.volume = if muted.0 {
+        Volume::SILENT
+    } else {
+        Volume::Linear(settings.master_volume)
+    };
+    **music_volume = Volume::Linear(settings.music_volume);
+    **sound_effect_volume = Volume::Linear(settings.sound_effect_volume);
+    **ui_volume = Volume::Linear(settings.ui_volume);
+}
+
+fn save_settings(settings: Res<Settings>, path: Res<SettingsPath>) {
+    if let Err(error) = settings.save(path.0.as_deref()) {
+        warn!("Failed to save settings: {error}");
+    }
+}
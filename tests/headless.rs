@@ -0,0 +1,360 @@
+//! Drive the game without a window, GPU or audio device: the whole game with
+//! [`AppPlugin::headless`] to go through the screens and menus, and only the
+//! gameplay on top of Bevy's `MinimalPlugins` for everything else.
+
+use std::time::{Duration, Instant};
+
+use bevy::{
+    app::PluginsState,
+    asset::AssetMetaCheck,
+    audio::AudioLoader,
+    input::InputPlugin,
+    picking::{
+        backend::HitData,
+        pointer::{Location, PointerButton, PointerId},
+    },
+    prelude::*,
+    render::camera::NormalizedRenderTarget,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+};
+use template_2d::{
+    AppPlugin, FIXED_TICK_RATE_HZ, GameplayPlugin, Pause,
+    cli::{Cli, StartScreen},
+    debug::log_file::LogDir,
+    demo::{
+        level::Level,
+        movement::PhysicalTranslation,
+        player::Player,
+        progression::{CurrentLevel, LoadLevel},
+    },
+    menus::Menu,
+    replay::{PlayReplay, Playback, RecordReplay, StopReplay},
+    save::SaveDir,
+    screens::Screen,
+    settings::SettingsPath,
+};
+
+/// Long enough to load all assets from disk.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The whole game, as the player would see it.
+fn headless_app(cli: Cli) -> App {
+    let mut app = App::new();
+    app.insert_resource(cli);
+    // Never touch the player's settings, logs or saves.
+    app.insert_resource(SettingsPath(None));
+    app.insert_resource(LogDir(None));
+    app.insert_resource(SaveDir(None));
+    app.add_plugins(AppPlugin::headless());
+    finish(app)
+}
+
+/// Only the gameplay, which is much quicker to start.
+fn gameplay_app(cli: Cli) -> App {
+    let mut app = App::new();
+    app.insert_resource(cli);
+    app.insert_resource(SaveDir(None));
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            meta_check: AssetMetaCheck::Never,
+            ..default()
+        },
+        StatesPlugin,
+        InputPlugin,
+        TransformPlugin,
+        ImagePlugin::default(),
+    ));
+    // Sounds and texture atlases are loaded, even though they are never played
+    // or drawn.
+    app.init_asset::<AudioSource>();
+    app.init_asset_loader::<AudioLoader>();
+    if !app
+        .world()
+        .contains_resource::<Assets<TextureAtlasLayout>>()
+    {
+        app.init_asset::<TextureAtlasLayout>();
+    }
+    app.add_plugins(GameplayPlugin);
+    finish(app)
+}
+
+/// Get the app ready to be updated by hand.
+fn finish(mut app: App) -> App {
+    // Step time by exactly one fixed tick per frame, so tests are deterministic.
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / FIXED_TICK_RATE_HZ,
+    )));
+    // `App::run` would do this before the first update.
+    while app.plugins_state() == PluginsState::Adding {
+        bevy::tasks::tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Update the app until `done` returns true, which may take a while if assets
+/// are being loaded.
+fn update_until(app: &mut App, mut done: impl FnMut(&mut World) -> bool) {
+    let start = Instant::now();
+    while !done(app.world_mut()) {
+        assert!(start.elapsed() < TIMEOUT, "gave up after {TIMEOUT:?}");
+        app.update();
+    }
+}
+
+fn screen(world: &World) -> Screen {
+    *world.resource::<State<Screen>>().get()
+}
+
+fn menu(world: &World) -> Menu {
+    *world.resource::<State<Menu>>().get()
+}
+
+/// The button with the text `text`, if it's shown.
+fn button(world: &mut World, text: &str) -> Option<Entity> {
+    world
+        .query::<(&Text, &ChildOf)>()
+        .iter(world)
+        .find(|(button_text, _)| button_text.0 == text)
+        .map(|(_, child_of)| child_of.parent())
+}
+
+/// Click the button with the text `text`, like the mouse would.
+fn click(world: &mut World, text: &str) {
+    let button = button(world, text).unwrap_or_else(|| panic!("there is no {text:?} button"));
+    world.trigger_targets(
+        Pointer::new(
+            PointerId::Mouse,
+            Location {
+                target: NormalizedRenderTarget::Image(Handle::<Image>::default().into()),
+                position: Vec2::ZERO,
+            },
+            button,
+            Click {
+                button: PointerButton::Primary,
+                hit: HitData::new(button, 0.0, None, None),
+                duration: Duration::ZERO,
+            },
+        ),
+        button,
+    );
+}
+
+fn player_position(world: &mut World) -> Option<Vec2> {
+    world
+        .query_filtered::<&Transform, With<Player>>()
+        .iter(world)
+        .next()
+        .map(|transform| transform.translation.xy())
+}
+
+fn player_translation(world: &mut World) -> Vec2 {
+    world
+        .query_filtered::<&PhysicalTranslation, With<Player>>()
+        .single(world)
+        .unwrap()
+        .0
+}
+
+/// The names of all spawned levels.
+fn level_names(world: &mut World) -> Vec<String> {
+    world
+        .query::<&Level>()
+        .iter(world)
+        .map(|level| level.name.clone())
+        .collect()
+}
+
+fn is_transitioning(world: &mut World) -> bool {
+    world
+        .query::<&Name>()
+        .iter(world)
+        .any(|name| name.as_str() == "Level Transition")
+}
+
+/// Wait until only the level `name` is spawned and the transition is over.
+fn update_until_level(app: &mut App, name: &str) {
+    update_until(app, |world| {
+        level_names(world) == [name] && !is_transitioning(world)
+    });
+}
+
+#[test]
+fn splash_and_title_screens_lead_to_gameplay() {
+    let mut app = headless_app(Cli::default());
+    app.update();
+    assert_eq!(screen(app.world()), Screen::Splash);
+
+    update_until(&mut app, |world| button(world, "Play").is_some());
+    assert_eq!(screen(app.world()), Screen::Title);
+    assert_eq!(menu(app.world()), Menu::Main);
+
+    click(app.world_mut(), "Play");
+    // The menu is closed on the way out of the title screen, which takes
+    // effect a frame after the level is spawned.
+    update_until(&mut app, |world| {
+        player_position(world).is_some() && menu(world) == Menu::None
+    });
+    assert_eq!(screen(app.world()), Screen::Gameplay);
+    assert!(button(app.world_mut(), "Play").is_none());
+}
+
+#[test]
+fn pause_menu_pauses_until_continued() {
+    let mut app = headless_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+    update_until(&mut app, |world| player_position(world).is_some());
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyP);
+    update_until(&mut app, |world| button(world, "Continue").is_some());
+    assert_eq!(menu(app.world()), Menu::Pause);
+    assert_eq!(*app.world().resource::<State<Pause>>().get(), Pause(true));
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(KeyCode::KeyP);
+    click(app.world_mut(), "Continue");
+    update_until(&mut app, |world| menu(world) == Menu::None);
+    app.update();
+    assert_eq!(*app.world().resource::<State<Pause>>().get(), Pause(false));
+}
+
+#[test]
+fn loading_leads_to_gameplay() {
+    let mut app = gameplay_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+    app.update();
+    assert_eq!(screen(app.world()), Screen::Loading);
+
+    update_until(&mut app, |world| screen(world) == Screen::Gameplay);
+}
+
+#[test]
+fn gameplay_spawns_player() {
+    let mut app = gameplay_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+
+    update_until(&mut app, |world| player_position(world).is_some());
+    assert_eq!(screen(app.world()), Screen::Gameplay);
+}
+
+#[test]
+fn player_moves_with_input() {
+    let mut app = gameplay_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+    update_until(&mut app, |world| player_position(world).is_some());
+    let start = player_position(app.world_mut()).unwrap();
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyD);
+    for _ in 0..32 {
+        app.update();
+    }
+
+    let end = player_position(app.world_mut()).unwrap();
+    assert!(
+        end.x > start.x,
+        "the player should have moved right, from {start} to {end}"
+    );
+}
+
+#[test]
+fn back_to_back_level_loads_end_on_the_last_one() {
+    let mut app = gameplay_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+    update_until_level(&mut app, "Duck Pond");
+
+    app.world_mut().trigger(LoadLevel::Id("meadow".to_string()));
+    app.world_mut().trigger(LoadLevel::Id("pond".to_string()));
+    app.update();
+    app.world_mut().trigger(LoadLevel::Id("meadow".to_string()));
+
+    update_until_level(&mut app, "Meadow");
+    assert_eq!(app.world().resource::<CurrentLevel>().0, 1);
+}
+
+#[test]
+fn level_load_during_fade_in_switches_again() {
+    let mut app = gameplay_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+    update_until_level(&mut app, "Duck Pond");
+
+    app.world_mut().trigger(LoadLevel::Id("meadow".to_string()));
+    // The new level is spawned while the screen is still black.
+    update_until(&mut app, |world| level_names(world) == ["Meadow"]);
+    assert!(is_transitioning(app.world_mut()));
+    app.world_mut().trigger(LoadLevel::Id("pond".to_string()));
+
+    update_until_level(&mut app, "Duck Pond");
+    assert_eq!(app.world().resource::<CurrentLevel>().0, 0);
+}
+
+#[test]
+fn replay_reproduces_the_recorded_movement() {
+    let mut app = gameplay_app(Cli {
+        screen: Some(StartScreen::Gameplay),
+        ..default()
+    });
+    update_until_level(&mut app, "Duck Pond");
+    let path = std::env::temp_dir().join(format!(
+        "template_2d_test_{}.replay.ron",
+        std::process::id()
+    ));
+
+    app.world_mut().trigger(RecordReplay {
+        path: Some(path.clone()),
+    });
+    update_until_level(&mut app, "Duck Pond");
+    for key in [KeyCode::KeyD, KeyCode::KeyW] {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+        for _ in 0..16 {
+            app.update();
+        }
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+    // Come to a stop before the recording ends.
+    for _ in 0..32 {
+        app.update();
+    }
+    let recorded = player_translation(app.world_mut());
+    app.world_mut().trigger(StopReplay);
+    app.update();
+
+    // Play back at a different tick rate than the one the game runs at.
+    app.world_mut()
+        .resource_mut::<Time<Fixed>>()
+        .set_timestep_hz(50.0);
+    app.world_mut().trigger(PlayReplay { path: path.clone() });
+    app.update();
+    assert!(app.world().contains_resource::<Playback>());
+    update_until(&mut app, |world| !world.contains_resource::<Playback>());
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(player_translation(app.world_mut()), recorded);
+    assert_eq!(
+        app.world().resource::<Time<Fixed>>().timestep(),
+        Duration::from_secs_f64(1.0 / 50.0)
+    );
+}